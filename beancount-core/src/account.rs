@@ -1,6 +1,10 @@
 use typed_builder::TypedBuilder;
 
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
 
 use super::account_types::AccountType;
 
@@ -22,6 +26,7 @@ use super::account_types::AccountType;
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.17ry42rqbuiu>
 #[derive(Clone, Debug, Eq, PartialEq, Hash, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Account<'a> {
     /// Type of the account.
     pub ty: AccountType,
@@ -29,3 +34,287 @@ pub struct Account<'a> {
     /// Optional parts of the account following the account type.
     pub parts: Vec<Cow<'a, str>>,
 }
+
+impl Account<'_> {
+    /// Copies the account so that it no longer borrows the parsed input.
+    pub fn into_owned(self) -> Account<'static> {
+        Account {
+            ty: self.ty,
+            parts: self.parts.into_iter().map(crate::owned).collect(),
+        }
+    }
+}
+
+impl<'a> Account<'a> {
+    /// The account one level up in the hierarchy, e.g. `Assets:US:BofA` for
+    /// `Assets:US:BofA:Checking`. Returns `None` for an account with no parts.
+    pub fn parent(&self) -> Option<Account<'a>> {
+        let (_, parts) = self.parts.split_last()?;
+        Some(Account {
+            ty: self.ty,
+            parts: parts.to_vec(),
+        })
+    }
+
+    /// The last part of the account, e.g. `Checking` for `Assets:US:BofA:Checking`. Returns `None`
+    /// for an account with no parts.
+    pub fn leaf(&self) -> Option<&str> {
+        self.parts.last().map(|part| &**part)
+    }
+
+    /// Whether `other` is in the subtree below this account, i.e. it has the same type and this
+    /// account's parts are a strict prefix of its parts. An account is not its own ancestor.
+    pub fn is_ancestor_of(&self, other: &Account<'_>) -> bool {
+        self.ty == other.ty
+            && self.parts.len() < other.parts.len()
+            && self.parts.iter().zip(&other.parts).all(|(a, b)| a == b)
+    }
+}
+
+impl Account<'static> {
+    /// Parses an account whose root is one of `root_names` instead of the default account type
+    /// names, as set with beancount's `name_assets`, `name_income`, ... options.
+    ///
+    /// # Example
+    /// ```rust
+    /// use beancount_core::{Account, AccountType};
+    /// let roots = vec![(AccountType::Income, "Indtægter".to_string())].into_iter().collect();
+    /// let account = Account::from_str_with_root_names("Indtægter:Løn", &roots).unwrap();
+    /// assert_eq!(account.ty, AccountType::Income);
+    /// assert_eq!(account.parts, vec!["Løn"]);
+    /// ```
+    pub fn from_str_with_root_names(
+        s: &str,
+        root_names: &HashMap<AccountType, String>,
+    ) -> Result<Self, ParseAccountError> {
+        parse_account(s, |root| {
+            root_names
+                .iter()
+                .find(|(_, name)| name.as_str() == root)
+                .map(|(ty, _)| *ty)
+        })
+    }
+}
+
+/// Parses an account written as in a beancount file, e.g. `Assets:US:BofA:Checking`. The root
+/// must be one of the default account type names; see
+/// [`from_str_with_root_names`](#method.from_str_with_root_names) for custom ones.
+///
+/// # Example
+/// ```rust
+/// use beancount_core::{Account, AccountType};
+/// let account: Account = "Assets:US:BofA:Checking".parse().unwrap();
+/// assert_eq!(account.ty, AccountType::Assets);
+/// assert_eq!(account.parts, vec!["US", "BofA", "Checking"]);
+/// ```
+impl FromStr for Account<'static> {
+    type Err = ParseAccountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_account(s, |root| root.parse().ok())
+    }
+}
+
+fn parse_account<F>(s: &str, account_type: F) -> Result<Account<'static>, ParseAccountError>
+where
+    F: Fn(&str) -> Option<AccountType>,
+{
+    if s.is_empty() {
+        return Err(ParseAccountError::Empty);
+    }
+    let mut components = s.split(':');
+    let root = components.next().unwrap_or_default();
+    let parts: Vec<_> = components.collect();
+    if parts.is_empty() {
+        return Err(ParseAccountError::MissingParts);
+    }
+    if !is_valid_component(root, char::is_uppercase) {
+        return Err(ParseAccountError::InvalidComponent(root.to_string()));
+    }
+    if let Some(part) = parts
+        .iter()
+        .find(|part| !is_valid_component(part, |c| c.is_uppercase() || c.is_numeric()))
+    {
+        return Err(ParseAccountError::InvalidComponent(part.to_string()));
+    }
+    let ty = account_type(root).ok_or_else(|| ParseAccountError::UnknownRoot(root.to_string()))?;
+    Ok(Account::builder()
+        .ty(ty)
+        .parts(
+            parts
+                .into_iter()
+                .map(|part| part.to_string().into())
+                .collect(),
+        )
+        .build())
+}
+
+/// Whether `s` is a valid component of an account name, mirroring the grammar: a first character
+/// accepted by `first`, followed by letters, combining marks, digits and dashes.
+fn is_valid_component<F: Fn(char) -> bool>(s: &str, first: F) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(first)
+        && chars.all(|c| c.is_alphanumeric() || c == '-' || is_combining_mark(c))
+}
+
+/// Whether `c` is in one of the Unicode blocks of combining diacritical marks, such as the U+0301
+/// in `Cafe\u{301}`.
+fn is_combining_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{300}'..='\u{36f}'
+            | '\u{1ab0}'..='\u{1aff}'
+            | '\u{1dc0}'..='\u{1dff}'
+            | '\u{20d0}'..='\u{20ff}'
+            | '\u{fe20}'..='\u{fe2f}'
+    )
+}
+
+/// An error returned when parsing an [`Account`](struct.Account.html) from a string fails.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseAccountError {
+    /// The string is empty.
+    Empty,
+    /// The account has no components after its root.
+    MissingParts,
+    /// A component is empty or contains characters not allowed in account names.
+    InvalidComponent(String),
+    /// The root is not the name of an account type.
+    UnknownRoot(String),
+}
+
+impl fmt::Display for ParseAccountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseAccountError::Empty => write!(f, "account name is empty"),
+            ParseAccountError::MissingParts => {
+                write!(f, "account name has no components after its root")
+            }
+            ParseAccountError::InvalidComponent(component) => {
+                write!(f, "invalid account name component '{}'", component)
+            }
+            ParseAccountError::UnknownRoot(root) => write!(f, "unknown root account '{}'", root),
+        }
+    }
+}
+
+impl error::Error for ParseAccountError {}
+
+/// Formats the account as its type's default name followed by its `:`-separated parts, e.g.
+/// `Assets:US:BofA:Checking`.
+impl fmt::Display for Account<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.ty.default_name())?;
+        for part in &self.parts {
+            write!(f, ":{}", part)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let account = |ty, parts: Vec<&'static str>| {
+            Account::builder()
+                .ty(ty)
+                .parts(parts.into_iter().map(Into::into).collect())
+                .build()
+        };
+        assert_eq!(account(AccountType::Equity, vec![]).to_string(), "Equity");
+        assert_eq!(
+            account(AccountType::Assets, vec!["US", "BofA", "Checking"]).to_string(),
+            "Assets:US:BofA:Checking"
+        );
+        assert_eq!(
+            account(AccountType::Expenses, vec!["Café"]).to_string(),
+            "Expenses:Café"
+        );
+    }
+
+    #[test]
+    fn from_str() {
+        let parse = |s: &str| s.parse::<Account<'static>>();
+        assert_eq!(parse("Assets:Foo").unwrap().parts, vec!["Foo"]);
+        assert_eq!(parse("Expenses:Q1").unwrap().parts, vec!["Q1"]);
+        assert_eq!(
+            parse("Expenses:Tax:2018").unwrap().parts,
+            vec!["Tax", "2018"]
+        );
+        assert_eq!(
+            parse("Assets:Cafe\u{301}").unwrap().parts,
+            vec!["Cafe\u{301}"]
+        );
+        assert_eq!(
+            parse("Assets:US:BofA:Checking").unwrap().to_string(),
+            "Assets:US:BofA:Checking"
+        );
+
+        assert_eq!(parse(""), Err(ParseAccountError::Empty));
+        assert_eq!(parse("Assets"), Err(ParseAccountError::MissingParts));
+        assert_eq!(
+            parse("Assets:"),
+            Err(ParseAccountError::InvalidComponent("".into()))
+        );
+        assert_eq!(
+            parse("Assets: Foo"),
+            Err(ParseAccountError::InvalidComponent(" Foo".into()))
+        );
+        assert_eq!(
+            parse("Expenses:tax"),
+            Err(ParseAccountError::InvalidComponent("tax".into()))
+        );
+        assert_eq!(
+            parse("Dash-dash:Dash-dash"),
+            Err(ParseAccountError::UnknownRoot("Dash-dash".into()))
+        );
+        assert_eq!(
+            parse("Indtægter:Foo"),
+            Err(ParseAccountError::UnknownRoot("Indtægter".into()))
+        );
+    }
+
+    #[test]
+    fn hierarchy() {
+        let checking: Account = "Assets:US:BofA:Checking".parse().unwrap();
+        let bofa = checking.parent().unwrap();
+        assert_eq!(bofa.to_string(), "Assets:US:BofA");
+        assert_eq!(checking.leaf(), Some("Checking"));
+        assert_eq!(bofa.leaf(), Some("BofA"));
+
+        let root = bofa.parent().and_then(|us| us.parent()).unwrap();
+        assert_eq!(root.to_string(), "Assets");
+        assert_eq!(root.parent(), None);
+        assert_eq!(root.leaf(), None);
+
+        assert!(bofa.is_ancestor_of(&checking));
+        assert!(root.is_ancestor_of(&checking));
+        assert!(!checking.is_ancestor_of(&bofa));
+        assert!(!checking.is_ancestor_of(&checking));
+        assert!(!root.is_ancestor_of(&root));
+
+        let savings: Account = "Assets:US:BofA:Savings".parse().unwrap();
+        assert!(bofa.is_ancestor_of(&savings));
+        assert!(!checking.is_ancestor_of(&savings));
+        let liabilities: Account = "Liabilities:US:BofA:Checking".parse().unwrap();
+        assert!(!bofa.is_ancestor_of(&liabilities));
+        assert!(!root.is_ancestor_of(&liabilities));
+    }
+
+    #[test]
+    fn from_str_with_root_names() {
+        let roots = vec![(AccountType::Income, "Indtægter".to_string())]
+            .into_iter()
+            .collect();
+        let account = Account::from_str_with_root_names("Indtægter:Foo", &roots).unwrap();
+        assert_eq!(account.ty, AccountType::Income);
+        assert_eq!(account.parts, vec!["Foo"]);
+        assert_eq!(
+            Account::from_str_with_root_names("Income:Foo", &roots),
+            Err(ParseAccountError::UnknownRoot("Income".into()))
+        );
+    }
+}
@@ -1,7 +1,12 @@
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
 /// Allowed account types.
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.17ry42rqbuiu>
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AccountType {
     Assets,
     Liabilities,
@@ -11,6 +16,12 @@ pub enum AccountType {
 }
 
 impl AccountType {
+    /// All account types, in the order beancount lists them.
+    pub fn all() -> [AccountType; 5] {
+        use AccountType::*;
+        [Assets, Liabilities, Equity, Income, Expenses]
+    }
+
     /// Get the default name for this account type.
     ///
     /// # Example
@@ -33,3 +44,52 @@ impl AccountType {
         }
     }
 }
+
+/// Parses the default name of an account type. Renamed root accounts (`option "name_assets"`)
+/// are not recognized.
+impl FromStr for AccountType {
+    type Err = ParseAccountTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AccountType::all()
+            .into_iter()
+            .find(|ty| ty.default_name() == s)
+            .ok_or_else(|| ParseAccountTypeError(s.to_string()))
+    }
+}
+
+/// An error returned when a string is not the default name of an account type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseAccountTypeError(pub String);
+
+impl fmt::Display for ParseAccountTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown account type '{}'", self.0)
+    }
+}
+
+impl error::Error for ParseAccountTypeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str() {
+        for ty in AccountType::all() {
+            assert_eq!(ty.default_name().parse(), Ok(ty));
+        }
+        assert_eq!(
+            "Liabilities".parse::<AccountType>(),
+            Ok(AccountType::Liabilities)
+        );
+        assert_eq!(
+            "assets".parse::<AccountType>(),
+            Err(ParseAccountTypeError("assets".into()))
+        );
+        assert_eq!(
+            "Activa".parse::<AccountType>().unwrap_err().to_string(),
+            "unknown account type 'Activa'"
+        );
+    }
+}
@@ -1,18 +1,252 @@
 use rust_decimal::Decimal;
+use std::borrow::Cow;
 use std::cmp;
 use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Neg, Sub};
+use std::str::FromStr;
 use typed_builder::TypedBuilder;
 
 use super::Currency;
 
 /// A number of units of a certain commodity.
-#[derive(Clone, Debug, Eq, PartialEq, TypedBuilder, Hash)]
+///
+/// Amounts compare and hash by their number and commodity only; the `raw` literal is a
+/// formatting hint and doesn't affect equality.
+#[derive(Clone, Debug, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Amount<'a> {
     /// The value of the amount.
     pub num: Decimal,
 
     /// The commodity of the amount.
     pub currency: Currency<'a>,
+
+    /// The number as written in the input, e.g. `1,234.50`, if the parser was asked to keep it.
+    /// Renderers write it in place of `num`.
+    #[builder(default)]
+    pub raw: Option<Cow<'a, str>>,
+}
+
+impl<'a> Amount<'a> {
+    /// Creates an amount of `num` units of `currency`.
+    pub fn new(num: Decimal, currency: impl Into<Currency<'a>>) -> Self {
+        Amount {
+            num,
+            currency: currency.into(),
+            raw: None,
+        }
+    }
+
+    /// Copies the amount so that it no longer borrows the parsed input.
+    pub fn into_owned(self) -> Amount<'static> {
+        Amount {
+            num: self.num,
+            currency: crate::owned(self.currency),
+            raw: self.raw.map(crate::owned),
+        }
+    }
+
+    /// Rounds the number to `dp` decimal places, rounding midpoints to the nearest even digit
+    /// like [`Decimal::round_dp`](https://docs.rs/rust_decimal/1/rust_decimal/struct.Decimal.html#method.round_dp).
+    pub fn round_dp(&self, dp: u32) -> Amount<'a> {
+        Amount::new(self.num.round_dp(dp), self.currency.clone())
+    }
+
+    /// Adds `other` to the amount, failing if the two are in different commodities or the sum
+    /// overflows.
+    pub fn checked_add(&self, other: &Amount<'a>) -> Result<Amount<'a>, AmountError<'a>> {
+        self.combine(other, Decimal::checked_add)
+    }
+
+    /// Subtracts `other` from the amount, failing if the two are in different commodities or the
+    /// difference overflows.
+    pub fn checked_sub(&self, other: &Amount<'a>) -> Result<Amount<'a>, AmountError<'a>> {
+        self.combine(other, Decimal::checked_sub)
+    }
+
+    fn combine(
+        &self,
+        other: &Amount<'a>,
+        op: fn(Decimal, Decimal) -> Option<Decimal>,
+    ) -> Result<Amount<'a>, AmountError<'a>> {
+        if self.currency != other.currency {
+            return Err(AmountError::CurrencyMismatch {
+                left: self.currency.clone(),
+                right: other.currency.clone(),
+            });
+        }
+        let num = op(self.num, other.num).ok_or(AmountError::Overflow)?;
+        Ok(Amount::new(num, self.currency.clone()))
+    }
+}
+
+/// An error returned when combining two amounts fails.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AmountError<'a> {
+    /// The amounts are in different commodities.
+    CurrencyMismatch {
+        /// Commodity of the left-hand amount.
+        left: Currency<'a>,
+        /// Commodity of the right-hand amount.
+        right: Currency<'a>,
+    },
+    /// The result is too large to be represented.
+    Overflow,
+}
+
+impl fmt::Display for AmountError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmountError::CurrencyMismatch { left, right } => {
+                write!(f, "can't combine amounts in {} and {}", left, right)
+            }
+            AmountError::Overflow => write!(f, "amount overflowed"),
+        }
+    }
+}
+
+impl error::Error for AmountError<'_> {}
+
+impl<'a> Add for Amount<'a> {
+    type Output = Result<Amount<'a>, AmountError<'a>>;
+
+    fn add(self, other: Amount<'a>) -> Self::Output {
+        self.checked_add(&other)
+    }
+}
+
+impl<'a> Add for &Amount<'a> {
+    type Output = Result<Amount<'a>, AmountError<'a>>;
+
+    fn add(self, other: &Amount<'a>) -> Self::Output {
+        self.checked_add(other)
+    }
+}
+
+impl<'a> Sub for Amount<'a> {
+    type Output = Result<Amount<'a>, AmountError<'a>>;
+
+    fn sub(self, other: Amount<'a>) -> Self::Output {
+        self.checked_sub(&other)
+    }
+}
+
+impl<'a> Sub for &Amount<'a> {
+    type Output = Result<Amount<'a>, AmountError<'a>>;
+
+    fn sub(self, other: &Amount<'a>) -> Self::Output {
+        self.checked_sub(other)
+    }
+}
+
+impl<'a> Neg for Amount<'a> {
+    type Output = Amount<'a>;
+
+    fn neg(self) -> Self::Output {
+        Amount::new(-self.num, self.currency)
+    }
+}
+
+impl<'a> Neg for &Amount<'a> {
+    type Output = Amount<'a>;
+
+    fn neg(self) -> Self::Output {
+        Amount::new(-self.num, self.currency.clone())
+    }
+}
+
+/// Parses an amount written as in a beancount file, e.g. `100.00 USD` or `1,000.00 USD`.
+///
+/// This is a restricted form of the amounts accepted in a ledger: the number must be a single
+/// literal, so arithmetic expressions such as `(1 + 2) USD` are rejected rather than evaluated.
+/// Commas are accepted as thousands separators, and numbers may use scientific notation (`1e3`).
+///
+/// # Example
+/// ```rust
+/// use beancount_core::Amount;
+/// let amount: Amount = "100.00 USD".parse().unwrap();
+/// assert_eq!(amount, Amount::new(rust_decimal::Decimal::new(10000, 2), "USD"));
+/// ```
+impl FromStr for Amount<'static> {
+    type Err = ParseAmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let num = parts.next().unwrap_or_default();
+        let currency = parts.next().ok_or(ParseAmountError::MissingCurrency)?;
+        if let Some(extra) = parts.next() {
+            return Err(ParseAmountError::InvalidCurrency(format!(
+                "{} {}",
+                currency, extra
+            )));
+        }
+        let digits = num.replace(',', "");
+        let parsed = if digits.contains(['e', 'E']) {
+            Decimal::from_scientific(&digits)
+        } else {
+            Decimal::from_str(&digits)
+        };
+        let num = parsed.map_err(|_| ParseAmountError::InvalidNumber(num.to_string()))?;
+        if !is_valid_currency(currency) {
+            return Err(ParseAmountError::InvalidCurrency(currency.to_string()));
+        }
+        Ok(Amount::new(num, currency.to_string()))
+    }
+}
+
+/// Whether `s` is a valid commodity name: 2 to 24 characters, starting with an uppercase ASCII
+/// letter and ending with an uppercase ASCII letter or digit, with `'`, `.`, `_` and `-` also
+/// allowed in between.
+fn is_valid_currency(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    let alphanumeric = |b: &u8| b.is_ascii_uppercase() || b.is_ascii_digit();
+    (2..=24).contains(&bytes.len())
+        && bytes[0].is_ascii_uppercase()
+        && bytes.last().is_some_and(alphanumeric)
+        && bytes.iter().all(|b| alphanumeric(b) || b"'._-".contains(b))
+}
+
+/// An error returned when parsing an [`Amount`](struct.Amount.html) from a string fails.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseAmountError {
+    /// The string has no commodity after the number.
+    MissingCurrency,
+    /// The number could not be parsed.
+    InvalidNumber(String),
+    /// The commodity is not a valid commodity name.
+    InvalidCurrency(String),
+}
+
+impl fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseAmountError::MissingCurrency => write!(f, "amount is missing a commodity"),
+            ParseAmountError::InvalidNumber(num) => write!(f, "invalid number '{}'", num),
+            ParseAmountError::InvalidCurrency(currency) => {
+                write!(f, "invalid commodity '{}'", currency)
+            }
+        }
+    }
+}
+
+impl error::Error for ParseAmountError {}
+
+impl PartialEq for Amount<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.num == other.num && self.currency == other.currency
+    }
+}
+
+impl Eq for Amount<'_> {}
+
+impl Hash for Amount<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.num.hash(state);
+        self.currency.hash(state);
+    }
 }
 
 impl cmp::PartialOrd for Amount<'_> {
@@ -26,7 +260,10 @@ impl cmp::PartialOrd for Amount<'_> {
 }
 
 /// An amount that may have missing units and/or commodity.
-#[derive(Clone, Debug, Eq, PartialEq, Hash, TypedBuilder)]
+///
+/// Like [`Amount`](struct.Amount.html), it compares and hashes without its `raw` literal.
+#[derive(Clone, Debug, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IncompleteAmount<'a> {
     /// The (optional) value of the amount.
     #[builder(default)]
@@ -35,6 +272,62 @@ pub struct IncompleteAmount<'a> {
     /// The (optional) commodity of the amount.
     #[builder(default)]
     pub currency: Option<Currency<'a>>,
+
+    /// The number as written in the input, e.g. `1,234.50`, if the parser was asked to keep it.
+    /// Renderers write it in place of `num`.
+    #[builder(default)]
+    pub raw: Option<Cow<'a, str>>,
+}
+
+impl<'a> IncompleteAmount<'a> {
+    /// Copies the amount so that it no longer borrows the parsed input.
+    pub fn into_owned(self) -> IncompleteAmount<'static> {
+        IncompleteAmount {
+            num: self.num,
+            currency: self.currency.map(crate::owned),
+            raw: self.raw.map(crate::owned),
+        }
+    }
+
+    /// Completes the amount, using `currency` if the amount has no commodity of its own.
+    ///
+    /// Returns `None` if the amount has no number.
+    pub fn complete_with(self, currency: Currency<'a>) -> Option<Amount<'a>> {
+        let num = self.num?;
+        Some(Amount {
+            num,
+            currency: self.currency.unwrap_or(currency),
+            raw: self.raw,
+        })
+    }
+}
+
+/// Formats the amount as it is written in a posting: the number and commodity separated by a
+/// space, whichever of the two is present, or nothing at all.
+impl fmt::Display for IncompleteAmount<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.num, &self.currency) {
+            (Some(num), Some(currency)) => write!(f, "{} {}", num, currency),
+            (None, Some(currency)) => write!(f, "{}", currency),
+            (Some(num), None) => write!(f, "{}", num),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl PartialEq for IncompleteAmount<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.num == other.num && self.currency == other.currency
+    }
+}
+
+impl Eq for IncompleteAmount<'_> {}
+
+impl Hash for IncompleteAmount<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.num.hash(state);
+        self.currency.hash(state);
+    }
 }
 
 impl cmp::PartialOrd for IncompleteAmount<'_> {
@@ -55,7 +348,8 @@ impl<'a> TryFrom<IncompleteAmount<'a>> for Amount<'a> {
             IncompleteAmount {
                 num: Some(num),
                 currency: Some(currency),
-            } => Ok(Amount { num, currency }),
+                raw,
+            } => Ok(Amount { num, currency, raw }),
             _ => Err(()),
         }
     }
@@ -66,6 +360,184 @@ impl<'a> From<Amount<'a>> for IncompleteAmount<'a> {
         IncompleteAmount {
             num: Some(val.num),
             currency: Some(val.currency),
+            raw: val.raw,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complete_with_num_only() {
+        let incomplete = IncompleteAmount::builder().num(Some(10.into())).build();
+        assert_eq!(
+            incomplete.complete_with("USD".into()),
+            Some(
+                Amount::builder()
+                    .num(10.into())
+                    .currency("USD".into())
+                    .build()
+            )
+        );
+    }
+
+    #[test]
+    fn complete_with_complete_amount_keeps_currency() {
+        let incomplete = IncompleteAmount::builder()
+            .num(Some(10.into()))
+            .currency(Some("EUR".into()))
+            .build();
+        assert_eq!(
+            incomplete.complete_with("USD".into()),
+            Some(
+                Amount::builder()
+                    .num(10.into())
+                    .currency("EUR".into())
+                    .build()
+            )
+        );
+    }
+
+    #[test]
+    fn complete_with_missing_num() {
+        let incomplete = IncompleteAmount::builder()
+            .currency(Some("EUR".into()))
+            .build();
+        assert_eq!(incomplete.complete_with("USD".into()), None);
+    }
+
+    #[test]
+    fn parse_amount() {
+        assert_eq!(
+            "100.00 USD".parse::<Amount>(),
+            Ok(Amount::new(Decimal::new(10000, 2), "USD"))
+        );
+        assert_eq!(
+            "  -5\tVANGUARD_500 ".parse::<Amount>(),
+            Ok(Amount::new((-5).into(), "VANGUARD_500"))
+        );
+        assert_eq!(
+            "1,000.00 USD".parse::<Amount>(),
+            Ok(Amount::new(Decimal::new(100000, 2), "USD"))
+        );
+        assert_eq!(
+            "1e3 USD".parse::<Amount>(),
+            Ok(Amount::new(1000.into(), "USD"))
+        );
+        assert_eq!(
+            "2.5E-2 USD".parse::<Amount>(),
+            Ok(Amount::new(Decimal::new(25, 3), "USD"))
+        );
+    }
+
+    #[test]
+    fn parse_amount_errors() {
+        assert_eq!(
+            "100.00".parse::<Amount>(),
+            Err(ParseAmountError::MissingCurrency)
+        );
+        assert_eq!(
+            "1O0 USD".parse::<Amount>(),
+            Err(ParseAmountError::InvalidNumber("1O0".into()))
+        );
+        assert_eq!(
+            "100 usd".parse::<Amount>(),
+            Err(ParseAmountError::InvalidCurrency("usd".into()))
+        );
+        assert_eq!(
+            "100 USD EUR".parse::<Amount>(),
+            Err(ParseAmountError::InvalidCurrency("USD EUR".into()))
+        );
+
+        // Expressions are only evaluated when parsing a ledger.
+        assert_eq!(
+            "1+2 USD".parse::<Amount>(),
+            Err(ParseAmountError::InvalidNumber("1+2".into()))
+        );
+        assert!("(1 + 2) USD".parse::<Amount>().is_err());
+    }
+
+    #[test]
+    fn display_incomplete_amount() {
+        let amount = |num: Option<Decimal>, currency: Option<&'static str>| {
+            IncompleteAmount::builder()
+                .num(num)
+                .currency(currency.map(Into::into))
+                .build()
+                .to_string()
+        };
+        let num = Some(Decimal::new(1050, 2));
+        assert_eq!(amount(num, Some("USD")), "10.50 USD");
+        assert_eq!(amount(None, Some("USD")), "USD");
+        assert_eq!(amount(num, None), "10.50");
+        assert_eq!(amount(None, None), "");
+    }
+
+    #[test]
+    fn arithmetic() -> Result<(), AmountError<'static>> {
+        let usd = |num: i64| Amount::new(Decimal::new(num, 2), "USD");
+        assert_eq!(usd(1050) + usd(-25), Ok(usd(1025)));
+        assert_eq!(&usd(1050) - &usd(25), Ok(usd(1025)));
+        assert_eq!(
+            usd(1000).checked_add(&usd(1))?.checked_sub(&usd(2))?,
+            usd(999)
+        );
+        assert_eq!(-usd(1050), usd(-1050));
+        assert_eq!(-&usd(-1050), usd(1050));
+        Ok(())
+    }
+
+    #[test]
+    fn currency_mismatch() {
+        let err = (Amount::new(1.into(), "USD") - Amount::new(1.into(), "EUR")).unwrap_err();
+        assert_eq!(
+            err,
+            AmountError::CurrencyMismatch {
+                left: "USD".into(),
+                right: "EUR".into()
+            }
+        );
+        assert_eq!(err.to_string(), "can't combine amounts in USD and EUR");
+    }
+
+    #[test]
+    fn raw_is_ignored_by_eq_and_hash() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let hash = |amount: &Amount<'_>| {
+            let mut hasher = DefaultHasher::new();
+            amount.hash(&mut hasher);
+            hasher.finish()
+        };
+        let plain = Amount::new(Decimal::new(100000, 2), "USD");
+        let mut raw = plain.clone();
+        raw.raw = Some("1,000.00".into());
+        assert_eq!(raw, plain);
+        assert_eq!(hash(&raw), hash(&plain));
+        assert_eq!(raw.partial_cmp(&plain), Some(cmp::Ordering::Equal));
+
+        assert_eq!(IncompleteAmount::from(raw), IncompleteAmount::from(plain));
+    }
+
+    #[test]
+    fn overflow() {
+        let max = Amount::new(Decimal::MAX, "USD");
+        let one = Amount::new(Decimal::ONE, "USD");
+        assert_eq!(max.checked_add(&one), Err(AmountError::Overflow));
+        assert_eq!(-max - one, Err(AmountError::Overflow));
+    }
+
+    #[test]
+    fn round_dp() {
+        let usd = |num: i64, scale: u32| Amount::new(Decimal::new(num, scale), "USD");
+        assert_eq!(usd(10_006, 3).round_dp(2), usd(1001, 2));
+        assert_eq!(usd(10_004, 3).round_dp(2), usd(1000, 2));
+        assert_eq!(usd(-10_006, 3).round_dp(2), usd(-1001, 2));
+        // Midpoints round to the nearest even digit.
+        assert_eq!(usd(10_005, 3).round_dp(2), usd(1000, 2));
+        assert_eq!(usd(10_015, 3).round_dp(2), usd(1002, 2));
+        assert_eq!(usd(5, 1).round_dp(2), usd(5, 1));
+    }
+}
@@ -0,0 +1,135 @@
+use std::ops::{Deref, DerefMut};
+
+use super::directives::Directive;
+use super::Ledger;
+
+/// A directive together with user data attached to it, so tools built on the AST can carry
+/// their own state (a reconciliation status, a source file, ...) alongside each directive.
+///
+/// Dereferences to the wrapped directive.
+///
+/// ```
+/// use beancount_core::{Annotated, Directive, Ledger, Pad};
+/// # use beancount_core::{Account, AccountType, Date};
+/// # let account = |name: &'static str| {
+/// #     Account::builder().ty(AccountType::Assets).parts(vec![name.into()]).build()
+/// # };
+/// # let pad = Pad::builder()
+/// #     .date(Date::from_str_unchecked("2020-01-01"))
+/// #     .pad_to_account(account("Cash"))
+/// #     .pad_from_account(account("Bank"))
+/// #     .build();
+/// # let ledger = Ledger::builder().directives(vec![Directive::Pad(pad)]).build();
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Status {
+///     Pending,
+///     Reconciled,
+/// }
+///
+/// let mut annotated: Vec<Annotated<'_, Status>> = ledger.annotate(|_| Status::Pending);
+/// annotated[0].data = Status::Reconciled;
+/// assert!(matches!(*annotated[0], Directive::Pad(_)));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotated<'a, T> {
+    pub directive: Directive<'a>,
+    pub data: T,
+}
+
+impl<'a, T> Annotated<'a, T> {
+    pub fn new(directive: Directive<'a>, data: T) -> Self {
+        Annotated { directive, data }
+    }
+
+    /// Drops the data, returning the directive.
+    pub fn into_directive(self) -> Directive<'a> {
+        self.directive
+    }
+
+    /// Replaces the data with the result of `f`, keeping the directive.
+    pub fn map<U, F>(self, f: F) -> Annotated<'a, U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Annotated::new(self.directive, f(self.data))
+    }
+}
+
+impl<'a, T> Deref for Annotated<'a, T> {
+    type Target = Directive<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.directive
+    }
+}
+
+impl<T> DerefMut for Annotated<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.directive
+    }
+}
+
+impl<'a> Ledger<'a> {
+    /// Consumes the ledger, attaching the data returned by `f` to each directive. The directives
+    /// keep their order.
+    pub fn annotate<T, F>(self, mut f: F) -> Vec<Annotated<'a, T>>
+    where
+        F: FnMut(&Directive<'a>) -> T,
+    {
+        self.directives
+            .into_iter()
+            .map(|directive| {
+                let data = f(&directive);
+                Annotated::new(directive, data)
+            })
+            .collect()
+    }
+
+    /// Builds a ledger from annotated directives, dropping their data.
+    pub fn from_annotated<T>(annotated: Vec<Annotated<'a, T>>) -> Self {
+        Ledger::builder()
+            .directives(
+                annotated
+                    .into_iter()
+                    .map(Annotated::into_directive)
+                    .collect(),
+            )
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::{assets, open};
+    use crate::*;
+
+    #[test]
+    fn annotate_round_trip() {
+        let ledger = Ledger::builder()
+            .directives(vec![
+                open("2020-01-01", assets("Cash")),
+                open("2020-01-02", assets("Bank")),
+            ])
+            .build();
+
+        let mut annotated = ledger.clone().annotate(|directive| {
+            directive
+                .date()
+                .map(|date| date.to_string())
+                .unwrap_or_default()
+        });
+        assert_eq!(annotated[0].data, "2020-01-01");
+        assert_eq!(annotated[1].data, "2020-01-02");
+        assert_eq!(
+            annotated[1].date(),
+            Some(&Date::from_str_unchecked("2020-01-02"))
+        );
+
+        annotated[0] = annotated[0]
+            .clone()
+            .map(|data| format!("{} reconciled", data));
+        assert_eq!(annotated[0].data, "2020-01-01 reconciled");
+        assert_eq!(Ledger::from_annotated(annotated), ledger);
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use super::account::Account;
+use super::amount::Amount;
+use super::directives::Directive;
+use super::{Currency, Date, Ledger};
+
+/// A `balance` directive whose expected amount doesn't match the account's actual balance.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BalanceDiscrepancy<'a> {
+    /// Date of the failing balance directive.
+    pub date: Date<'a>,
+
+    /// Account whose balance was asserted.
+    pub account: Account<'a>,
+
+    /// Amount asserted by the balance directive.
+    pub expected: Amount<'a>,
+
+    /// Balance of the account in the asserted commodity at the start of `date`.
+    pub actual: Amount<'a>,
+
+    /// Indices into [`Ledger::directives`](../struct.Ledger.html#structfield.directives) of the
+    /// transactions that posted to the account before `date`, in date order.
+    pub contributing: Vec<usize>,
+}
+
+/// Running balance of a single account.
+#[derive(Default)]
+struct AccountBalance<'a> {
+    units: HashMap<Currency<'a>, Decimal>,
+    /// Position in date order and directive index of every transaction posting to the account.
+    transactions: Vec<(usize, usize)>,
+}
+
+/// Checks every `balance` directive against the running balance of its account.
+///
+/// Like beancount, a balance directive asserts the balance at the *start* of its date, so
+/// transactions on the same date are not included. An elided posting amount is inferred with
+/// [`Transaction::solved_units`](../struct.Transaction.html#method.solved_units), and ignored if
+/// it can't be. An assertion passes if the balance is within the directive's tolerance.
+pub fn verify_balances<'a>(ledger: &Ledger<'a>) -> Vec<BalanceDiscrepancy<'a>> {
+    let mut order: Vec<usize> = (0..ledger.directives.len())
+        .filter(|&i| ledger.directives[i].date().is_some())
+        .collect();
+    // Stable sort by date, with balance directives before any other directive on the same date.
+    order.sort_by_key(|&i| {
+        let directive = &ledger.directives[i];
+        (
+            directive.date(),
+            !matches!(directive, Directive::Balance(_)),
+        )
+    });
+
+    let mut balances: HashMap<&Account<'a>, AccountBalance<'_>> = HashMap::new();
+    let mut discrepancies = Vec::new();
+    for (position, i) in order.into_iter().enumerate() {
+        match &ledger.directives[i] {
+            Directive::Transaction(txn) => {
+                for posting in &txn.postings {
+                    let state = balances.entry(&posting.account).or_default();
+                    if state.transactions.last() != Some(&(position, i)) {
+                        state.transactions.push((position, i));
+                    }
+                }
+                for (posting, units) in txn.solved_units() {
+                    let state = balances.entry(&posting.account).or_default();
+                    *state.units.entry(units.currency).or_default() += units.num;
+                }
+            }
+            Directive::Balance(balance) => {
+                // The balances of the asserted account, and of its sub-accounts if inclusive.
+                let states: Vec<_> = balances
+                    .iter()
+                    .filter(|(account, _)| {
+                        **account == &balance.account
+                            || balance.inclusive && is_sub_account(account, &balance.account)
+                    })
+                    .map(|(_, state)| state)
+                    .collect();
+                let actual: Decimal = states
+                    .iter()
+                    .filter_map(|s| s.units.get(balance.amount.currency.as_ref()))
+                    .sum();
+                let tolerance = balance.tolerance.unwrap_or_default();
+                if (actual - balance.amount.num).abs() > tolerance {
+                    discrepancies.push(BalanceDiscrepancy {
+                        date: balance.date.clone(),
+                        account: balance.account.clone(),
+                        expected: balance.amount.clone(),
+                        actual: Amount::new(actual, balance.amount.currency.clone()),
+                        contributing: contributing(&states),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    discrepancies
+}
+
+/// Whether `account` is a strict sub-account of `parent`.
+fn is_sub_account(account: &Account<'_>, parent: &Account<'_>) -> bool {
+    account.ty == parent.ty
+        && account.parts.len() > parent.parts.len()
+        && account.parts.starts_with(&parent.parts)
+}
+
+/// The transactions that posted to any of `states`, in date order.
+fn contributing(states: &[&AccountBalance<'_>]) -> Vec<usize> {
+    let mut transactions: Vec<_> = states
+        .iter()
+        .flat_map(|s| s.transactions.iter().copied())
+        .collect();
+    transactions.sort_unstable();
+    transactions.dedup();
+    transactions.into_iter().map(|(_, i)| i).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{account, assets, balance, elided, posting, transaction};
+    use crate::*;
+
+    /// A transaction on `date` moving `num` USD from `from` to `to`.
+    fn transfer(
+        date: &'static str,
+        to: &'static str,
+        from: &'static str,
+        num: i64,
+    ) -> Directive<'static> {
+        Directive::Transaction(transaction(
+            date,
+            vec![
+                posting(assets(to), num, "USD"),
+                posting(assets(from), -num, "USD"),
+            ],
+        ))
+    }
+
+    #[test]
+    fn passing_assertion() {
+        let ledger = Ledger::builder()
+            .directives(vec![
+                transfer("2020-01-01", "Checking", "Savings", 100),
+                balance("2020-01-02", assets("Checking"), 100, "USD"),
+            ])
+            .build();
+        assert_eq!(verify_balances(&ledger), vec![]);
+    }
+
+    #[test]
+    fn failing_assertion_reports_contributing_transactions() {
+        let ledger = Ledger::builder()
+            .directives(vec![
+                transfer("2020-01-01", "Checking", "Savings", 100),
+                transfer("2020-01-03", "Checking", "Savings", 10),
+                transfer("2020-01-02", "Savings", "Cash", 5),
+                transfer("2020-01-02", "Cash", "Checking", 20),
+                // Transactions on the assertion date are not included.
+                transfer("2020-01-04", "Checking", "Cash", 1),
+                balance("2020-01-04", assets("Checking"), 100, "USD"),
+            ])
+            .build();
+        let discrepancies = verify_balances(&ledger);
+        assert_eq!(discrepancies.len(), 1);
+        let discrepancy = &discrepancies[0];
+        assert_eq!(discrepancy.account, assets("Checking"));
+        assert_eq!(discrepancy.expected, Amount::new(100.into(), "USD"));
+        assert_eq!(discrepancy.actual, Amount::new(90.into(), "USD"));
+        assert_eq!(discrepancy.contributing, vec![0, 3, 1]);
+    }
+
+    #[test]
+    fn tolerance() {
+        let balance_with_tolerance = |num: i64, tolerance: i64| {
+            let mut directive = balance(
+                "2020-01-02",
+                assets("Checking"),
+                Decimal::new(num, 2),
+                "USD",
+            );
+            if let Directive::Balance(balance) = &mut directive {
+                balance.tolerance = Some(Decimal::new(tolerance, 2));
+            }
+            directive
+        };
+        let ledger = Ledger::builder()
+            .directives(vec![
+                transfer("2020-01-01", "Checking", "Savings", 100),
+                balance_with_tolerance(10002, 2),
+                balance_with_tolerance(10003, 2),
+            ])
+            .build();
+        let discrepancies = verify_balances(&ledger);
+        assert_eq!(discrepancies.len(), 1);
+        assert_eq!(
+            discrepancies[0].expected,
+            Amount::new(Decimal::new(10003, 2), "USD")
+        );
+        assert_eq!(discrepancies[0].actual, Amount::new(100.into(), "USD"));
+        assert_eq!(
+            discrepancies[0].date,
+            Date::from_str_unchecked("2020-01-02")
+        );
+    }
+
+    #[test]
+    fn elided_amount_is_inferred() {
+        let ledger = Ledger::builder()
+            .directives(vec![
+                Directive::Transaction(transaction(
+                    "2020-01-01",
+                    vec![
+                        posting(assets("Checking"), 100, "USD"),
+                        elided(assets("Savings")),
+                    ],
+                )),
+                balance("2020-01-02", assets("Savings"), -100, "USD"),
+            ])
+            .build();
+        assert_eq!(verify_balances(&ledger), vec![]);
+    }
+
+    #[test]
+    fn only_asserted_commodity_is_compared() {
+        let ledger = Ledger::builder()
+            .directives(vec![
+                transfer("2020-01-01", "Checking", "Savings", 100),
+                Directive::Transaction(transaction(
+                    "2020-01-01",
+                    vec![
+                        posting(assets("Checking"), 50, "EUR"),
+                        posting(assets("Savings"), -50, "EUR"),
+                    ],
+                )),
+                balance("2020-01-02", assets("Checking"), 100, "USD"),
+            ])
+            .build();
+        assert_eq!(verify_balances(&ledger), vec![]);
+    }
+
+    #[test]
+    fn inclusive_assertion_covers_sub_accounts() {
+        let cash = |parts: &[&'static str]| account(AccountType::Assets, parts);
+        let inclusive_balance = |num: i64| {
+            let mut directive = balance("2020-01-02", cash(&["Cash"]), num, "USD");
+            if let Directive::Balance(balance) = &mut directive {
+                balance.inclusive = true;
+            }
+            directive
+        };
+        let ledger = Ledger::builder()
+            .directives(vec![
+                Directive::Transaction(transaction(
+                    "2020-01-01",
+                    vec![
+                        posting(cash(&["Cash", "Wallet"]), 10, "USD"),
+                        posting(cash(&["Cash", "Jar"]), 5, "USD"),
+                        posting(cash(&["Cash"]), 1, "USD"),
+                        posting(cash(&["Cashback"]), -16, "USD"),
+                    ],
+                )),
+                inclusive_balance(16),
+                inclusive_balance(15),
+                balance("2020-01-02", assets("Cash"), 1, "USD"),
+            ])
+            .build();
+        let discrepancies = verify_balances(&ledger);
+        assert_eq!(discrepancies.len(), 1);
+        assert_eq!(discrepancies[0].actual, Amount::new(16.into(), "USD"));
+        assert_eq!(discrepancies[0].contributing, vec![0]);
+    }
+}
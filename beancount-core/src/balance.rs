@@ -0,0 +1,234 @@
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+use super::amount::Amount;
+use super::directives::Transaction;
+use super::posting::Posting;
+use super::Currency;
+
+/// An error returned when a transaction can't be balanced by
+/// [`Transaction::balance`](../struct.Transaction.html#method.balance).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BalanceError<'a> {
+    /// More than one posting has no number, so their amounts can't be inferred.
+    MultipleElided(usize),
+    /// The weight of the posting at this index can't be computed, because its amount, cost or
+    /// price has no commodity.
+    IncompleteWeight(usize),
+    /// The postings don't sum to zero and the remainder, given by commodity, can't be absorbed
+    /// by a single elided posting.
+    Unbalanced(Vec<Amount<'a>>),
+}
+
+impl fmt::Display for BalanceError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BalanceError::MultipleElided(count) => {
+                write!(f, "{} postings have no amount, at most one may", count)
+            }
+            BalanceError::IncompleteWeight(i) => {
+                write!(f, "the weight of posting {} can't be computed", i)
+            }
+            BalanceError::Unbalanced(residual) => {
+                write!(f, "transaction does not balance: ")?;
+                for (i, amount) in residual.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} {}", amount.num, amount.currency)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for BalanceError<'_> {}
+
+impl<'a> Transaction<'a> {
+    /// Fills in the number of the posting without one, so that the weights of the postings sum to
+    /// zero in every commodity. The elided posting takes the commodity of the remainder if it has
+    /// none of its own.
+    ///
+    /// Weights must sum to exactly zero; no tolerance is applied. A transaction without an elided
+    /// posting is only checked.
+    pub fn balance(&mut self) -> Result<(), BalanceError<'a>> {
+        let elided: Vec<usize> = (0..self.postings.len())
+            .filter(|&i| self.postings[i].units.num.is_none())
+            .collect();
+        if elided.len() > 1 {
+            return Err(BalanceError::MultipleElided(elided.len()));
+        }
+
+        // Remainder by commodity, in order of first appearance.
+        let mut residual: Vec<(Currency<'a>, Decimal)> = Vec::new();
+        for (i, posting) in self.postings.iter().enumerate() {
+            if elided.contains(&i) {
+                continue;
+            }
+            let weight = posting.weight().ok_or(BalanceError::IncompleteWeight(i))?;
+            match residual.iter_mut().find(|(c, _)| *c == weight.currency) {
+                Some((_, num)) => *num += weight.num,
+                None => residual.push((weight.currency, weight.num)),
+            }
+        }
+        residual.retain(|(_, num)| !num.is_zero());
+
+        let elided = match elided.first() {
+            Some(&i) => &mut self.postings[i].units,
+            None if residual.is_empty() => return Ok(()),
+            None => return Err(unbalanced(residual)),
+        };
+        let (currency, num) = match (residual.len(), &elided.currency) {
+            (0, Some(currency)) => (currency.clone(), Decimal::ZERO),
+            (1, Some(currency)) if *currency != residual[0].0 => return Err(unbalanced(residual)),
+            (1, _) => residual.remove(0),
+            _ => return Err(unbalanced(residual)),
+        };
+        elided.num = Some(-num);
+        elided.currency = Some(currency);
+        Ok(())
+    }
+
+    /// The units of every posting, with the amount of an elided posting inferred as by
+    /// [`balance`](#method.balance). Postings whose units can't be determined, because they have
+    /// no commodity or the transaction doesn't balance, are left out.
+    pub fn solved_units(&self) -> Vec<(&Posting<'a>, Amount<'a>)> {
+        let elided = self
+            .postings
+            .iter()
+            .position(|posting| posting.units.num.is_none());
+        let inferred = elided.and_then(|elided| {
+            let mut balanced = self.clone();
+            balanced.balance().ok()?;
+            Amount::try_from(balanced.postings.swap_remove(elided).units).ok()
+        });
+        self.postings
+            .iter()
+            .enumerate()
+            .filter_map(|(i, posting)| {
+                let units = if Some(i) == elided {
+                    inferred.clone()?
+                } else {
+                    Amount::try_from(posting.units.clone()).ok()?
+                };
+                Some((posting, units))
+            })
+            .collect()
+    }
+}
+
+fn unbalanced(residual: Vec<(Currency<'_>, Decimal)>) -> BalanceError<'_> {
+    BalanceError::Unbalanced(
+        residual
+            .into_iter()
+            .map(|(currency, num)| Amount::new(num, currency))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{account, elided, posting, transaction};
+    use crate::*;
+
+    fn credit_card() -> Posting<'static> {
+        posting(
+            account(AccountType::Liabilities, &["CreditCard"]),
+            Decimal::new(-3745, 2),
+            "USD",
+        )
+    }
+
+    fn restaurant() -> Account<'static> {
+        account(AccountType::Expenses, &["Restaurant"])
+    }
+
+    #[test]
+    fn elided_posting() {
+        let mut txn = transaction("2014-05-05", vec![credit_card(), elided(restaurant())]);
+        txn.balance().unwrap();
+        assert_eq!(
+            txn.postings[1].units,
+            Amount::new(Decimal::new(3745, 2), "USD").into()
+        );
+
+        // Balancing again only checks the now complete transaction.
+        txn.balance().unwrap();
+    }
+
+    #[test]
+    fn solved_units() {
+        let txn = transaction("2014-05-05", vec![credit_card(), elided(restaurant())]);
+        let units: Vec<_> = txn
+            .solved_units()
+            .into_iter()
+            .map(|(posting, units)| (posting.account.to_string(), units))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (
+                    "Liabilities:CreditCard".to_string(),
+                    Amount::new(Decimal::new(-3745, 2), "USD")
+                ),
+                (
+                    "Expenses:Restaurant".to_string(),
+                    Amount::new(Decimal::new(3745, 2), "USD")
+                ),
+            ]
+        );
+
+        // The elided amount is left out if the transaction can't be balanced.
+        let mut txn = txn;
+        txn.postings
+            .push(elided(account(AccountType::Expenses, &["Tips"])));
+        assert_eq!(txn.solved_units().len(), 1);
+    }
+
+    #[test]
+    fn multiple_elided_postings() {
+        let mut txn = transaction(
+            "2014-05-05",
+            vec![
+                credit_card(),
+                elided(restaurant()),
+                elided(account(AccountType::Expenses, &["Tips"])),
+            ],
+        );
+        assert_eq!(txn.balance(), Err(BalanceError::MultipleElided(2)));
+    }
+
+    #[test]
+    fn unbalanced() {
+        let mut txn = transaction(
+            "2014-05-05",
+            vec![credit_card(), posting(restaurant(), 30, "USD")],
+        );
+        assert_eq!(
+            txn.balance(),
+            Err(BalanceError::Unbalanced(vec![Amount::new(
+                Decimal::new(-745, 2),
+                "USD"
+            )]))
+        );
+
+        // An elided posting can't absorb a remainder in two commodities.
+        let mut txn = transaction(
+            "2014-05-05",
+            vec![
+                credit_card(),
+                posting(account(AccountType::Assets, &["Cash"]), -10, "EUR"),
+                elided(restaurant()),
+            ],
+        );
+        assert!(matches!(
+            txn.balance(),
+            Err(BalanceError::Unbalanced(residual)) if residual.len() == 2
+        ));
+    }
+}
@@ -1,5 +1,9 @@
 use std::borrow::Cow;
-use std::{fmt, fmt::Display};
+use std::cmp::Ordering;
+#[cfg(feature = "chrono")]
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::{error, fmt, fmt::Display};
 
 #[cfg(feature = "chrono")]
 use chrono::NaiveDate;
@@ -7,6 +11,9 @@ use chrono::NaiveDate;
 /// Represents a beancount date. It can be created using the `from_*_unchecked` methods.
 /// Alternatively, with the `chrono` feature enabled, it can be converted from a `NaiveDate`.
 ///
+/// Dates compare by calendar date, so `2019/01/01` equals `2019-01-01`. Dates that aren't made of
+/// a numeric year, month and day compare by their text, after all numeric dates.
+///
 /// # Example
 /// ```rust
 /// use beancount_core::Date;
@@ -20,32 +27,196 @@ use chrono::NaiveDate;
 /// #[cfg(feature = "chrono")]
 /// let today: Date<'static> = chrono::Local::today().naive_local().into();
 /// ```
-#[derive(Eq, PartialEq, Debug, Clone, Ord, PartialOrd, Hash)]
-pub struct Date<'a>(Cow<'a, str>);
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "Cow<'a, str>", into = "Cow<'a, str>")
+)]
+pub struct Date<'a> {
+    text: Cow<'a, str>,
+    /// The year, month and day of the date if they are numbers, parsed once when the date is
+    /// created. Dates compare and hash by these, or by their text if they aren't numbers.
+    numeric: Option<(i64, u32, u32)>,
+}
+
+impl<'a> Date<'a> {
+    fn new(text: Cow<'a, str>) -> Date<'a> {
+        let mut parts = text.split(['-', '/']);
+        let numeric = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(year), Some(month), Some(day), None) => year
+                .parse()
+                .ok()
+                .zip(month.parse().ok())
+                .zip(day.parse().ok())
+                .map(|((year, month), day)| (year, month, day)),
+            _ => None,
+        };
+        Date { text, numeric }
+    }
+}
 
 impl Date<'_> {
     pub fn from_str_unchecked(s: &str) -> Date<'_> {
-        Date(s.into())
+        Date::new(s.into())
     }
 
     pub fn from_string_unchecked(s: String) -> Date<'static> {
-        Date(s.into())
+        Date::new(s.into())
     }
 
     pub fn from_cow_unchecked(s: Cow<'_, str>) -> Date<'_> {
-        Date(s)
+        Date::new(s)
+    }
+
+    /// Copies the date so that it no longer borrows the parsed input.
+    pub fn into_owned(self) -> Date<'static> {
+        Date {
+            text: Cow::Owned(self.text.into_owned()),
+            numeric: self.numeric,
+        }
+    }
+
+    /// Creates a date from its year, month and day, formatted as `YYYY-MM-DD`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use beancount_core::Date;
+    /// assert_eq!(Date::from_ymd(2020, 2, 29).unwrap().to_string(), "2020-02-29");
+    /// assert!(Date::from_ymd(2021, 2, 29).is_err());
+    /// ```
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Result<Date<'static>, DateError> {
+        if !(0..=9999).contains(&year) {
+            return Err(DateError::InvalidYear(year));
+        }
+        if !(1..=12).contains(&month) {
+            return Err(DateError::InvalidMonth(month));
+        }
+        if !(1..=days_in_month(year, month)).contains(&day) {
+            return Err(DateError::InvalidDay { year, month, day });
+        }
+        Ok(Date::from_string_unchecked(format!(
+            "{:04}-{:02}-{:02}",
+            year, month, day
+        )))
+    }
+
+    /// The year of the date, or `None` if the date isn't a valid numeric date.
+    pub fn year(&self) -> Option<i32> {
+        self.ymd().map(|(year, _, _)| year)
+    }
+
+    /// The month of the date, from 1 to 12, or `None` if the date isn't a valid numeric date.
+    pub fn month(&self) -> Option<u32> {
+        self.ymd().map(|(_, month, _)| month)
+    }
+
+    /// The day of the month of the date, from 1, or `None` if the date isn't a valid numeric
+    /// date.
+    pub fn day(&self) -> Option<u32> {
+        self.ymd().map(|(_, _, day)| day)
+    }
+
+    /// The year, month and day of the date, if they are numbers that make up a calendar date.
+    fn ymd(&self) -> Option<(i32, u32, u32)> {
+        let (year, month, day) = self.numeric?;
+        let year = i32::try_from(year).ok()?;
+        if (1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day) {
+            Some((year, month, day))
+        } else {
+            None
+        }
+    }
+}
+
+/// An error returned by [`Date::from_ymd`](struct.Date.html#method.from_ymd) for a year, month
+/// and day that don't make up a date, or when converting a date that can't be parsed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DateError {
+    /// The date isn't a numeric year, month and day that make up a calendar date.
+    Unparseable(String),
+    /// The year can't be written with four digits.
+    InvalidYear(i32),
+    /// The month is not between 1 and 12.
+    InvalidMonth(u32),
+    /// The month doesn't have this day.
+    InvalidDay { year: i32, month: u32, day: u32 },
+}
+
+impl fmt::Display for DateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateError::Unparseable(date) => write!(f, "invalid date '{}'", date),
+            DateError::InvalidYear(year) => write!(f, "invalid year {}", year),
+            DateError::InvalidMonth(month) => write!(f, "invalid month {}", month),
+            DateError::InvalidDay { year, month, day } => {
+                write!(f, "invalid day {} for {:04}-{:02}", day, year, month)
+            }
+        }
+    }
+}
+
+impl error::Error for DateError {}
+
+/// The number of days in `month` (1 to 12) of `year` in the Gregorian calendar.
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl PartialEq for Date<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Date<'_> {}
+
+impl PartialOrd for Date<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Date<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.numeric, other.numeric) {
+            (Some(date), Some(other)) => date.cmp(&other),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => self.text.cmp(&other.text),
+        }
+    }
+}
+
+impl Hash for Date<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.numeric {
+            Some(date) => date.hash(state),
+            None => self.text.hash(state),
+        }
     }
 }
 
 impl<'a> From<Date<'a>> for Cow<'a, str> {
     fn from(d: Date<'a>) -> Self {
-        d.0
+        d.text
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Date<'a> {
+    fn from(s: Cow<'a, str>) -> Self {
+        Date::new(s)
     }
 }
 
 impl Display for Date<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0.fmt(f)
+        self.text.fmt(f)
     }
 }
 
@@ -56,6 +227,32 @@ impl From<NaiveDate> for Date<'_> {
     }
 }
 
+/// Parses the date, accepting both `-` and `/` as separators.
+#[cfg(feature = "chrono")]
+impl TryFrom<&Date<'_>> for NaiveDate {
+    type Error = DateError;
+
+    fn try_from(d: &Date<'_>) -> Result<Self, Self::Error> {
+        d.ymd()
+            .and_then(|(year, month, day)| NaiveDate::from_ymd_opt(year, month, day))
+            .ok_or_else(|| DateError::Unparseable(d.to_string()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_date_to_chrono() {
+    let date = |s| NaiveDate::try_from(&Date::from_str_unchecked(s));
+    let expected = NaiveDate::from_ymd_opt(2020, 5, 5).unwrap();
+    assert_eq!(date("2020-05-05"), Ok(expected));
+    assert_eq!(date("2020/05/05"), Ok(expected));
+    assert_eq!(
+        date("2020-02-30"),
+        Err(DateError::Unparseable("2020-02-30".into()))
+    );
+    assert!(date("yesterday").is_err());
+}
+
 #[cfg(feature = "chrono")]
 #[test]
 fn test_date_from_chrono() {
@@ -64,3 +261,85 @@ fn test_date_from_chrono() {
         Date::from_str_unchecked("2020-05-05")
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_across_separators() {
+        let date = Date::from_str_unchecked;
+        assert!(date("1979/01/01") < date("2019-01-01"));
+        assert!(date("2019/01/01") < date("2019-02-01"));
+        assert!(date("2019-12-31") < date("2020/01/01"));
+        assert_eq!(date("2019/01/01").cmp(&date("2019-01-01")), Ordering::Equal);
+        assert_eq!(date("2019/01/01"), date("2019-01-01"));
+        let hashed: std::collections::HashSet<_> =
+            [date("2019/01/01"), date("2019-01-01"), date("2019-1-1")].into();
+        assert_eq!(hashed.len(), 1);
+        assert!(date("43020-01-01") > date("2020-01-01"));
+        // Non-numeric dates sort after numeric ones.
+        assert!(date("not a date") > date("9999-12-31"));
+
+        let mut dates = [date("2019-02-01"), date("2019/01/15"), date("2018-12-31")];
+        dates.sort();
+        assert_eq!(
+            dates.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["2018-12-31", "2019/01/15", "2019-02-01"]
+        );
+    }
+
+    #[test]
+    fn components() {
+        for s in &["2020-03-14", "2020/03/14"] {
+            let date = Date::from_str_unchecked(s);
+            assert_eq!(date.year(), Some(2020));
+            assert_eq!(date.month(), Some(3));
+            assert_eq!(date.day(), Some(14));
+        }
+        assert_eq!(Date::from_str_unchecked("2020-02-29").day(), Some(29));
+
+        for s in &[
+            "2020-13-01",
+            "2019-02-29",
+            "2020-01",
+            "2020-aa-01",
+            "2020-01-01-01",
+        ] {
+            let date = Date::from_str_unchecked(s);
+            assert_eq!(date.year(), None, "{}", s);
+            assert_eq!(date.month(), None, "{}", s);
+            assert_eq!(date.day(), None, "{}", s);
+        }
+    }
+
+    #[test]
+    fn from_ymd() {
+        assert_eq!(
+            Date::from_ymd(2020, 1, 5),
+            Ok(Date::from_str_unchecked("2020-01-05"))
+        );
+        assert_eq!(
+            Date::from_ymd(2020, 1, 5).unwrap().to_string(),
+            "2020-01-05"
+        );
+        assert_eq!(Date::from_ymd(2020, 12, 31).unwrap().day(), Some(31));
+        assert_eq!(
+            Date::from_ymd(2020, 13, 1),
+            Err(DateError::InvalidMonth(13))
+        );
+        assert_eq!(Date::from_ymd(2020, 0, 1), Err(DateError::InvalidMonth(0)));
+        assert_eq!(
+            Date::from_ymd(2020, 2, 30),
+            Err(DateError::InvalidDay {
+                year: 2020,
+                month: 2,
+                day: 30
+            })
+        );
+        assert!(Date::from_ymd(2020, 4, 31).is_err());
+        assert!(Date::from_ymd(1900, 2, 29).is_err());
+        assert!(Date::from_ymd(2000, 2, 29).is_ok());
+        assert_eq!(Date::from_ymd(-1, 1, 1), Err(DateError::InvalidYear(-1)));
+    }
+}
@@ -1,6 +1,9 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
 use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
 
 use rust_decimal::Decimal;
 use typed_builder::TypedBuilder;
@@ -8,12 +11,13 @@ use typed_builder::TypedBuilder;
 use super::account::Account;
 use super::amount::Amount;
 use super::flags::Flag;
-use super::metadata::{Link, Meta, Tag};
+use super::metadata::{owned_meta, Link, Meta, MetaValue, Tag};
 use super::posting::Posting;
-use super::{Currency, Date};
+use super::{owned, owned_set, Currency, Date};
 
 /// The set of booking methods for positions on accounts.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Booking {
     /// Reject ambiguous matches with an error.
     Strict,
@@ -35,24 +39,72 @@ pub enum Booking {
     Lifo,
 }
 
+impl Booking {
+    /// The name of the booking method as written in an `open` directive or the `booking_method`
+    /// option, e.g. `STRICT_WITH_SIZE`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Booking::Strict => "STRICT",
+            Booking::StrictWithSize => "STRICT_WITH_SIZE",
+            Booking::None => "NONE",
+            Booking::Average => "AVERAGE",
+            Booking::Fifo => "FIFO",
+            Booking::Lifo => "LIFO",
+        }
+    }
+}
+
+/// Formats the booking method by its [`name`](#method.name).
+///
+/// # Example
+/// ```rust
+/// use beancount_core::Booking;
+/// assert_eq!(Booking::StrictWithSize.to_string(), "STRICT_WITH_SIZE");
+/// assert_eq!("FIFO".parse(), Ok(Booking::Fifo));
+/// assert!("fifo".parse::<Booking>().is_err());
+/// ```
+impl fmt::Display for Booking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses a booking method from its [`name`](#method.name), which is uppercase as in beancount.
+impl FromStr for Booking {
+    type Err = ParseBookingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Booking::*;
+        [Strict, StrictWithSize, None, Average, Fifo, Lifo]
+            .into_iter()
+            .find(|booking| booking.name() == s)
+            .ok_or_else(|| ParseBookingError(s.to_string()))
+    }
+}
+
 impl<'a> TryFrom<&'a str> for Booking {
     type Error = ();
 
     fn try_from(val: &'a str) -> Result<Self, Self::Error> {
-        match val {
-            "STRICT" => Ok(Booking::Strict),
-            "STRICT_WITH_SIZE" => Ok(Booking::StrictWithSize),
-            "NONE" => Ok(Booking::None),
-            "AVERAGE" => Ok(Booking::Average),
-            "FIFO" => Ok(Booking::Fifo),
-            "LIFO" => Ok(Booking::Lifo),
-            _ => Err(()),
-        }
+        val.parse().map_err(|_| ())
+    }
+}
+
+/// An error returned when a string is not the name of a booking method.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseBookingError(pub String);
+
+impl fmt::Display for ParseBookingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown booking method '{}'", self.0)
     }
 }
 
+impl error::Error for ParseBookingError {}
+
 /// Enum of all directive types.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Directive<'a> {
     Open(Open<'a>),
     Close(Close<'a>),
@@ -69,7 +121,185 @@ pub enum Directive<'a> {
     Price(Price<'a>),
     Query(Query<'a>),
     Transaction(Transaction<'a>),
-    Unsupported,
+    /// A line the parser recognizes but doesn't model, such as an org-mode heading (`* Cash`),
+    /// with its text as written.
+    Unsupported(Cow<'a, str>),
+}
+
+impl<'a> Directive<'a> {
+    /// The kind of directive, as the keyword it is written with (`open`, `balance`, ...).
+    /// Transactions are `transaction` and unsupported directives `unsupported`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Directive::Open(_) => "open",
+            Directive::Close(_) => "close",
+            Directive::Balance(_) => "balance",
+            Directive::Option(_) => "option",
+            Directive::Commodity(_) => "commodity",
+            Directive::Custom(_) => "custom",
+            Directive::Document(_) => "document",
+            Directive::Event(_) => "event",
+            Directive::Include(_) => "include",
+            Directive::Note(_) => "note",
+            Directive::Pad(_) => "pad",
+            Directive::Plugin(_) => "plugin",
+            Directive::Price(_) => "price",
+            Directive::Query(_) => "query",
+            Directive::Transaction(_) => "transaction",
+            Directive::Unsupported(_) => "unsupported",
+        }
+    }
+
+    /// Date of the directive, or `None` for directives that aren't dated (`option`, `plugin`,
+    /// `include` and unsupported directives).
+    pub fn date(&self) -> Option<&Date<'a>> {
+        match self {
+            Directive::Open(d) => Some(&d.date),
+            Directive::Close(d) => Some(&d.date),
+            Directive::Balance(d) => Some(&d.date),
+            Directive::Commodity(d) => Some(&d.date),
+            Directive::Custom(d) => Some(&d.date),
+            Directive::Document(d) => Some(&d.date),
+            Directive::Event(d) => Some(&d.date),
+            Directive::Note(d) => Some(&d.date),
+            Directive::Pad(d) => Some(&d.date),
+            Directive::Price(d) => Some(&d.date),
+            Directive::Query(d) => Some(&d.date),
+            Directive::Transaction(d) => Some(&d.date),
+            Directive::Option(_)
+            | Directive::Include(_)
+            | Directive::Plugin(_)
+            | Directive::Unsupported(_) => None,
+        }
+    }
+
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Directive<'static> {
+        match self {
+            Directive::Open(d) => Directive::Open(d.into_owned()),
+            Directive::Close(d) => Directive::Close(d.into_owned()),
+            Directive::Balance(d) => Directive::Balance(d.into_owned()),
+            Directive::Option(d) => Directive::Option(d.into_owned()),
+            Directive::Commodity(d) => Directive::Commodity(d.into_owned()),
+            Directive::Custom(d) => Directive::Custom(d.into_owned()),
+            Directive::Document(d) => Directive::Document(d.into_owned()),
+            Directive::Event(d) => Directive::Event(d.into_owned()),
+            Directive::Include(d) => Directive::Include(d.into_owned()),
+            Directive::Note(d) => Directive::Note(d.into_owned()),
+            Directive::Pad(d) => Directive::Pad(d.into_owned()),
+            Directive::Plugin(d) => Directive::Plugin(d.into_owned()),
+            Directive::Price(d) => Directive::Price(d.into_owned()),
+            Directive::Query(d) => Directive::Query(d.into_owned()),
+            Directive::Transaction(d) => Directive::Transaction(d.into_owned()),
+            Directive::Unsupported(text) => Directive::Unsupported(owned(text)),
+        }
+    }
+
+    /// Metadata of the directive, or `None` for directives that don't carry metadata (`option`,
+    /// `plugin`, `include` and unsupported directives).
+    ///
+    /// # Example
+    /// ```rust
+    /// use beancount_core::metadata::MetaValue;
+    /// use beancount_core::{Account, AccountType, BcOption, Close, Date, Directive};
+    ///
+    /// let close = Directive::Close(
+    ///     Close::builder()
+    ///         .date(Date::from_str_unchecked("2016-11-28"))
+    ///         .account(
+    ///             Account::builder()
+    ///                 .ty(AccountType::Assets)
+    ///                 .parts(vec!["Cash".into()])
+    ///                 .build(),
+    ///         )
+    ///         .meta(
+    ///             vec![("reason".into(), MetaValue::Text("moved".into()))]
+    ///                 .into_iter()
+    ///                 .collect(),
+    ///         )
+    ///         .build(),
+    /// );
+    /// assert_eq!(close.date(), Some(&Date::from_str_unchecked("2016-11-28")));
+    /// assert_eq!(close.meta().unwrap()["reason"], MetaValue::Text("moved".into()));
+    ///
+    /// let option = Directive::Option(
+    ///     BcOption::builder()
+    ///         .name("title".into())
+    ///         .val("Books".into())
+    ///         .build(),
+    /// );
+    /// assert_eq!(option.date(), None);
+    /// assert_eq!(option.meta(), None);
+    /// ```
+    pub fn meta(&self) -> Option<&Meta<'a>> {
+        match self {
+            Directive::Open(d) => Some(&d.meta),
+            Directive::Close(d) => Some(&d.meta),
+            Directive::Balance(d) => Some(&d.meta),
+            Directive::Commodity(d) => Some(&d.meta),
+            Directive::Custom(d) => Some(&d.meta),
+            Directive::Document(d) => Some(&d.meta),
+            Directive::Event(d) => Some(&d.meta),
+            Directive::Note(d) => Some(&d.meta),
+            Directive::Pad(d) => Some(&d.meta),
+            Directive::Price(d) => Some(&d.meta),
+            Directive::Query(d) => Some(&d.meta),
+            Directive::Transaction(d) => Some(&d.meta),
+            Directive::Option(_)
+            | Directive::Include(_)
+            | Directive::Plugin(_)
+            | Directive::Unsupported(_) => None,
+        }
+    }
+
+    /// The directive's text in the parsed input, if it was parsed. For
+    /// [`Directive::Unsupported`](enum.Directive.html#variant.Unsupported) this is its text while
+    /// it borrows the input.
+    pub fn source(&self) -> Option<&'a str> {
+        match self {
+            Directive::Open(d) => d.source,
+            Directive::Close(d) => d.source,
+            Directive::Balance(d) => d.source,
+            Directive::Option(d) => d.source,
+            Directive::Commodity(d) => d.source,
+            Directive::Custom(d) => d.source,
+            Directive::Document(d) => d.source,
+            Directive::Event(d) => d.source,
+            Directive::Include(d) => d.source,
+            Directive::Note(d) => d.source,
+            Directive::Pad(d) => d.source,
+            Directive::Plugin(d) => d.source,
+            Directive::Price(d) => d.source,
+            Directive::Query(d) => d.source,
+            Directive::Transaction(d) => d.source,
+            Directive::Unsupported(Cow::Borrowed(text)) => Some(text),
+            Directive::Unsupported(Cow::Owned(_)) => None,
+        }
+    }
+
+    /// Number of blank lines that preceded the directive in the parsed input. Always `0` for
+    /// [`Directive::Unsupported`](enum.Directive.html#variant.Unsupported).
+    pub fn blank_lines_before(&self) -> u8 {
+        match self {
+            Directive::Open(d) => d.blank_lines_before,
+            Directive::Close(d) => d.blank_lines_before,
+            Directive::Balance(d) => d.blank_lines_before,
+            Directive::Option(d) => d.blank_lines_before,
+            Directive::Commodity(d) => d.blank_lines_before,
+            Directive::Custom(d) => d.blank_lines_before,
+            Directive::Document(d) => d.blank_lines_before,
+            Directive::Event(d) => d.blank_lines_before,
+            Directive::Include(d) => d.blank_lines_before,
+            Directive::Note(d) => d.blank_lines_before,
+            Directive::Pad(d) => d.blank_lines_before,
+            Directive::Plugin(d) => d.blank_lines_before,
+            Directive::Price(d) => d.blank_lines_before,
+            Directive::Query(d) => d.blank_lines_before,
+            Directive::Transaction(d) => d.blank_lines_before,
+            Directive::Unsupported(_) => 0,
+        }
+    }
 }
 
 /// Represents a `balance` directive, which is a way for you to input your statement balance into
@@ -93,8 +323,19 @@ pub enum Directive<'a> {
 /// 2014-08-09 balance Assets:Cash      60.00 EUR
 /// ```
 ///
+/// The expected amount may be followed by an explicit tolerance, written after a `~`. The
+/// assertion then passes if the balance is within the tolerance of the expected amount:
+///
+/// ```text
+/// 2014-08-09 balance Assets:Cash     562.00 ~ 0.50 USD
+/// ```
+///
+/// Each directive asserts a single commodity; an account holding several commodities needs one
+/// directive per commodity.
+///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.l0pvgeniwvq8>
 #[derive(Clone, Debug, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Balance<'a> {
     /// Date of the balance.
     pub date: Date<'a>,
@@ -105,19 +346,47 @@ pub struct Balance<'a> {
     /// Amount to balance.
     pub amount: Amount<'a>,
 
+    /// Explicit tolerance written after a `~`, or `None` if the directive has none.
     #[builder(default)]
     pub tolerance: Option<Decimal>,
 
+    /// Whether the balance includes the sub-accounts of `account`, written as
+    /// `Assets:Cash:* 100 USD`. This is a non-standard extension to beancount.
+    #[builder(default)]
+    pub inclusive: bool,
+
     // diff_amount: Option<Amount>,
     /// Metadata attached to the balance directive.
     #[builder(default)]
     pub meta: Meta<'a>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Balance<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Balance<'static> {
+        Balance {
+            date: self.date.into_owned(),
+            account: self.account.into_owned(),
+            amount: self.amount.into_owned(),
+            tolerance: self.tolerance,
+            inclusive: self.inclusive,
+            meta: owned_meta(self.meta),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 /// Represents a Beancount `option`, which are configuration points global to the file.
 ///
 /// The general format of the `option` directive is:
@@ -135,6 +404,7 @@ pub struct Balance<'a> {
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.e2iyrfrmstl>
 
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BcOption<'a> {
     /// Name of the option.
     pub name: Cow<'a, str>,
@@ -142,11 +412,29 @@ pub struct BcOption<'a> {
     /// Value of the option.
     pub val: Cow<'a, str>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl BcOption<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> BcOption<'static> {
+        BcOption {
+            name: owned(self.name),
+            val: owned(self.val),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 impl<'a> BcOption<'a> {
     /// Determines if the current option specifies a root account name change.
     /// For example, the following line will rename the 'Assets' root account to 'Activa':
@@ -167,6 +455,71 @@ impl<'a> BcOption<'a> {
             _ => None,
         }
     }
+
+    /// Classifies the option into one of the standard beancount options, or returns `None` for
+    /// options this crate doesn't know and for known options with an invalid value.
+    ///
+    /// ```
+    /// use beancount_core::{AccountType, BcOption, Booking, KnownOption};
+    /// let option = |name: &'static str, val: &'static str| {
+    ///     BcOption::builder().name(name.into()).val(val.into()).build()
+    /// };
+    ///
+    /// assert_eq!(
+    ///     option("operating_currency", "USD").parsed(),
+    ///     Some(KnownOption::OperatingCurrency("USD".into()))
+    /// );
+    /// assert_eq!(
+    ///     option("booking_method", "FIFO").parsed(),
+    ///     Some(KnownOption::BookingMethod(Booking::Fifo))
+    /// );
+    /// assert_eq!(
+    ///     option("render_commas", "TRUE").parsed(),
+    ///     Some(KnownOption::RenderCommas(true))
+    /// );
+    /// assert_eq!(
+    ///     option("name_assets", "Activa").parsed(),
+    ///     Some(KnownOption::RootNameChange(AccountType::Assets, "Activa".into()))
+    /// );
+    /// assert_eq!(option("booking_method", "RANDOM").parsed(), None);
+    /// assert_eq!(option("inferred_tolerance_default", "*:0.001").parsed(), None);
+    /// ```
+    pub fn parsed(&self) -> Option<KnownOption<'a>> {
+        if let Some((ty, name)) = self.root_name_change() {
+            return Some(KnownOption::RootNameChange(ty, name));
+        }
+        match self.name.as_ref() {
+            "title" => Some(KnownOption::Title(self.val.clone())),
+            "operating_currency" => Some(KnownOption::OperatingCurrency(self.val.clone())),
+            "booking_method" => self
+                .val
+                .parse::<Booking>()
+                .ok()
+                .map(KnownOption::BookingMethod),
+            "render_commas" => match self.val.to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" => Some(KnownOption::RenderCommas(true)),
+                "false" | "0" | "no" => Some(KnownOption::RenderCommas(false)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// A standard beancount option with its value, as classified by
+/// [`BcOption::parsed`](struct.BcOption.html#method.parsed).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KnownOption<'a> {
+    /// `title`: the title of the ledger.
+    Title(Cow<'a, str>),
+    /// `operating_currency`: a currency reports are converted to. May be given several times.
+    OperatingCurrency(Currency<'a>),
+    /// `booking_method`: the default booking method of accounts.
+    BookingMethod(Booking),
+    /// `render_commas`: whether numbers are rendered with thousands separators.
+    RenderCommas(bool),
+    /// `name_assets`, `name_liabilities`, ...: the name of a root account.
+    RootNameChange(crate::AccountType, String),
 }
 
 /// Represents a `close` directive.  This directive signifies the closing of an account.
@@ -186,6 +539,7 @@ impl<'a> BcOption<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.wf248e8stnac>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Close<'a> {
     /// Date the account was closed.
     pub date: Date<'a>,
@@ -197,11 +551,30 @@ pub struct Close<'a> {
     #[builder(default)]
     pub meta: Meta<'a>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Close<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Close<'static> {
+        Close {
+            date: self.date.into_owned(),
+            account: self.account.into_owned(),
+            meta: owned_meta(self.meta),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 /// Represents a `commodity` directive.  This directive allows you to declare commodities,
 /// although doing so is not required in order to use a commodity.
 ///
@@ -228,6 +601,7 @@ pub struct Close<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.a3si01ejc035>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Commodity<'a> {
     /// Date the commodity was declared.
     pub date: Date<'a>,
@@ -239,11 +613,30 @@ pub struct Commodity<'a> {
     #[builder(default)]
     pub meta: Meta<'a>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Commodity<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Commodity<'static> {
+        Commodity {
+            date: self.date.into_owned(),
+            name: owned(self.name),
+            meta: owned_meta(self.meta),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 /// Represents a `custom` directive, which is a generic directive provided to allow clients to
 /// experiment with new features, e.g., budgeting.
 ///
@@ -270,6 +663,7 @@ pub struct Commodity<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.20klpeqb6ajy>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Custom<'a> {
     /// Date associated with the custom directive.
     pub date: Date<'a>,
@@ -277,18 +671,40 @@ pub struct Custom<'a> {
     /// Custom directive name.
     pub name: Cow<'a, str>,
 
-    /// Arbitrary number of custom directive arguments.
-    pub args: Vec<Cow<'a, str>>,
+    /// Arbitrary number of custom directive arguments. String arguments are
+    /// [`MetaValue::Text`](metadata/enum.MetaValue.html#variant.Text) without their quotes, like
+    /// `name`.
+    pub args: Vec<MetaValue<'a>>,
 
     /// Metadata attached to the custom directive.
     #[builder(default)]
     pub meta: Meta<'a>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Custom<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Custom<'static> {
+        Custom {
+            date: self.date.into_owned(),
+            name: owned(self.name),
+            args: self.args.into_iter().map(MetaValue::into_owned).collect(),
+            meta: owned_meta(self.meta),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 /// Represents a `document` directive.  A `document` directive can be used to attach an external
 /// file to the journal of an account.
 ///
@@ -306,6 +722,7 @@ pub struct Custom<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.w1ins9jk4mq3>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Document<'a> {
     /// Date the document was linked.
     pub date: Date<'a>,
@@ -328,11 +745,33 @@ pub struct Document<'a> {
     #[builder(default)]
     pub meta: Meta<'a>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Document<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Document<'static> {
+        Document {
+            date: self.date.into_owned(),
+            account: self.account.into_owned(),
+            path: owned(self.path),
+            tags: owned_set(self.tags),
+            links: owned_set(self.links),
+            meta: owned_meta(self.meta),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 /// Represents an `event` directive.  `event` directives are used to track the value of some
 /// variable of your choice over time - for example, your location.
 ///
@@ -350,6 +789,7 @@ pub struct Document<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.tm5fxddlik5x>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Event<'a> {
     /// Date the event occurred.
     pub date: Date<'a>,
@@ -364,11 +804,31 @@ pub struct Event<'a> {
     #[builder(default)]
     pub meta: Meta<'a>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Event<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Event<'static> {
+        Event {
+            date: self.date.into_owned(),
+            name: owned(self.name),
+            description: owned(self.description),
+            meta: owned_meta(self.meta),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 /// Represents an `include` directive.  The `include` directive, as it sounds, includes another
 /// Beancount file into the current one, allowing you to arbitrarily split up your ledger files.
 ///
@@ -386,15 +846,33 @@ pub struct Event<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.86lelow4097r>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Include<'a> {
     /// Fully qualified filename, including any necessary path segments.
     pub filename: Cow<'a, str>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Include<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Include<'static> {
+        Include {
+            filename: owned(self.filename),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 /// Represents a `note` directive.  A `note` directive is simply used to attach a dated comment to
 /// the journal of a particular account.
 ///
@@ -412,6 +890,7 @@ pub struct Include<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.c4cyaa6o6rqm>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Note<'a> {
     /// Date of the note.
     pub date: Date<'a>,
@@ -426,11 +905,31 @@ pub struct Note<'a> {
     #[builder(default)]
     pub meta: Meta<'a>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Note<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Note<'static> {
+        Note {
+            date: self.date.into_owned(),
+            account: self.account.into_owned(),
+            comment: owned(self.comment),
+            meta: owned_meta(self.meta),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 /// Represents a `open` directive.  This directive signifies the opening of an account.
 ///
 /// Some examples of the `open` directive:
@@ -446,6 +945,7 @@ pub struct Note<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.omdgvaikswd0>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Open<'a> {
     /// Date the account was opened.
     pub date: Date<'a>,
@@ -467,11 +967,32 @@ pub struct Open<'a> {
     #[builder(default)]
     pub meta: Meta<'a>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Open<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Open<'static> {
+        Open {
+            date: self.date.into_owned(),
+            account: self.account.into_owned(),
+            currencies: self.currencies.into_iter().map(owned).collect(),
+            booking: self.booking,
+            meta: owned_meta(self.meta),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 /// Represents a `pad` directive.  A `pad` directive automatically inserts a transaction that will
 /// make the subsequent balance assertion succeed, if it is needed.
 ///
@@ -493,6 +1014,7 @@ pub struct Open<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.aw8ic3d8k8rq>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pad<'a> {
     /// Date of the pad.
     pub date: Date<'a>,
@@ -507,11 +1029,31 @@ pub struct Pad<'a> {
     #[builder(default)]
     pub meta: Meta<'a>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Pad<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Pad<'static> {
+        Pad {
+            date: self.date.into_owned(),
+            pad_to_account: self.pad_to_account.into_owned(),
+            pad_from_account: self.pad_from_account.into_owned(),
+            meta: owned_meta(self.meta),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 /// Represents a `plugin` directive.
 ///
 /// In the Python version of Beancount, this would allow you to
@@ -533,6 +1075,7 @@ pub struct Pad<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.lxgs9ewvbt8k>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Plugin<'a> {
     /// Full module name of the plugin.
     pub module: Cow<'a, str>,
@@ -541,11 +1084,29 @@ pub struct Plugin<'a> {
     #[builder(default)]
     pub config: Option<Cow<'a, str>>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Plugin<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Plugin<'static> {
+        Plugin {
+            module: owned(self.module),
+            config: self.config.map(owned),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 /// Represents a `price` directive, which establishes the rate of exchange between one commodity and
 /// another.
 ///
@@ -574,6 +1135,7 @@ pub struct Plugin<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.f78ym1dxtemh>
 #[derive(Clone, Debug, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Price<'a> {
     /// Date of the price specification.
     pub date: Date<'a>,
@@ -588,11 +1150,56 @@ pub struct Price<'a> {
     #[builder(default)]
     pub meta: Meta<'a>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Price<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Price<'static> {
+        Price {
+            date: self.date.into_owned(),
+            currency: owned(self.currency),
+            amount: self.amount.into_owned(),
+            meta: owned_meta(self.meta),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
+impl<'a> Price<'a> {
+    /// Creates a price directive stating that one unit of the `base` commodity was worth `quote`
+    /// on `date`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use beancount_core::{Amount, Date, Price};
+    ///
+    /// let price = Price::from_rate(
+    ///     Date::from_str_unchecked("2014-07-09"),
+    ///     "HOOL",
+    ///     Amount::new(579.into(), "USD"),
+    /// );
+    /// assert_eq!(price.currency, "HOOL");
+    /// assert_eq!(price.amount.currency, "USD");
+    /// ```
+    pub fn from_rate(date: Date<'a>, base: impl Into<Currency<'a>>, quote: Amount<'a>) -> Self {
+        Price::builder()
+            .date(date)
+            .currency(base.into())
+            .amount(quote)
+            .build()
+    }
+}
+
 /// Represents a `query` directive.  `query` directives allow you to insert a query in the usual
 /// stream of transactions.
 ///
@@ -614,6 +1221,7 @@ pub struct Price<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.nw8fgvy4ub1w>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Query<'a> {
     /// Date on which the query should be run.
     pub date: Date<'a>,
@@ -628,11 +1236,31 @@ pub struct Query<'a> {
     #[builder(default)]
     pub meta: Meta<'a>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     /// Source string from the parsed input
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
 
+impl Query<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Query<'static> {
+        Query {
+            date: self.date.into_owned(),
+            name: owned(self.name),
+            query_string: owned(self.query_string),
+            meta: owned_meta(self.meta),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
 /// Represents a `txn` (or `*` or `!`) directive.
 ///
 /// A transaction can be signified by any of those three symbols, where `txn` and `*` both indicate
@@ -679,6 +1307,7 @@ pub struct Query<'a> {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.up4dj751q84w>
 #[derive(Clone, Debug, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transaction<'a> {
     pub date: Date<'a>,
 
@@ -712,6 +1341,67 @@ pub struct Transaction<'a> {
     #[builder(default)]
     pub meta: Meta<'a>,
 
+    /// Number of blank lines preceding the directive in the parsed input.
+    #[builder(default)]
+    pub blank_lines_before: u8,
+
     #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub source: Option<&'a str>,
 }
+
+impl Transaction<'_> {
+    /// Copies the directive so that it no longer borrows the parsed input. The `source` is
+    /// dropped.
+    pub fn into_owned(self) -> Transaction<'static> {
+        Transaction {
+            date: self.date.into_owned(),
+            flag: self.flag.into_owned(),
+            payee: self.payee.map(owned),
+            narration: owned(self.narration),
+            tags: owned_set(self.tags),
+            links: owned_set(self.links),
+            postings: self.postings.into_iter().map(Posting::into_owned).collect(),
+            meta: owned_meta(self.meta),
+            blank_lines_before: self.blank_lines_before,
+            source: None,
+        }
+    }
+}
+
+impl Transaction<'_> {
+    /// A one-line summary of the transaction: its date, flag, payee and narration, followed by
+    /// the number of postings.
+    ///
+    /// # Example
+    /// ```rust
+    /// use beancount_core::{Date, Flag, Transaction};
+    /// let txn = Transaction::builder()
+    ///     .date(Date::from_str_unchecked("2020-01-01"))
+    ///     .narration("Lunch".into())
+    ///     .build();
+    /// assert_eq!(txn.summary_line(), r#"2020-01-01 * "Lunch" (0 postings)"#);
+    ///
+    /// let txn = Transaction::builder()
+    ///     .date(Date::from_str_unchecked("2020-01-01"))
+    ///     .flag(Flag::Warning)
+    ///     .payee(Some("Cafe".into()))
+    ///     .narration("Lunch".into())
+    ///     .build();
+    /// assert_eq!(txn.summary_line(), r#"2020-01-01 ! "Cafe" "Lunch" (0 postings)"#);
+    /// ```
+    pub fn summary_line(&self) -> String {
+        let payee = match &self.payee {
+            Some(payee) => format!(" \"{}\"", payee),
+            None => String::new(),
+        };
+        let postings = match self.postings.len() {
+            1 => "1 posting".to_string(),
+            n => format!("{} postings", n),
+        };
+        format!(
+            "{} {}{} \"{}\" ({})",
+            self.date, self.flag, payee, self.narration, postings
+        )
+    }
+}
@@ -0,0 +1,78 @@
+//! Factories for the ledgers built by the unit tests of this crate.
+
+use rust_decimal::Decimal;
+
+use crate::*;
+
+/// An account of type `ty` made of `parts`.
+pub fn account(ty: AccountType, parts: &[&'static str]) -> Account<'static> {
+    Account::builder()
+        .ty(ty)
+        .parts(parts.iter().map(|&p| p.into()).collect())
+        .build()
+}
+
+/// The `Assets` account with the single part `name`, e.g. `Assets:Cash`.
+pub fn assets(name: &'static str) -> Account<'static> {
+    account(AccountType::Assets, &[name])
+}
+
+/// An amount of `num` units of `currency`, as written in a posting or price.
+pub fn amount(num: impl Into<Decimal>, currency: &'static str) -> IncompleteAmount<'static> {
+    Amount::new(num.into(), currency).into()
+}
+
+/// A posting of `num` units of `currency` to `account`.
+pub fn posting(
+    account: Account<'static>,
+    num: impl Into<Decimal>,
+    currency: &'static str,
+) -> Posting<'static> {
+    Posting::builder()
+        .account(account)
+        .units(amount(num, currency))
+        .build()
+}
+
+/// A posting to `account` whose amount is elided.
+pub fn elided(account: Account<'static>) -> Posting<'static> {
+    Posting::builder()
+        .account(account)
+        .units(IncompleteAmount::builder().build())
+        .build()
+}
+
+/// A transaction on `date` with an empty narration.
+pub fn transaction(date: &'static str, postings: Vec<Posting<'static>>) -> Transaction<'static> {
+    Transaction::builder()
+        .date(Date::from_str_unchecked(date))
+        .narration("".into())
+        .postings(postings)
+        .build()
+}
+
+/// An `open` directive for `account` on `date`.
+pub fn open(date: &'static str, account: Account<'static>) -> Directive<'static> {
+    Directive::Open(
+        Open::builder()
+            .date(Date::from_str_unchecked(date))
+            .account(account)
+            .build(),
+    )
+}
+
+/// A `balance` directive asserting `num` units of `currency` in `account` on `date`.
+pub fn balance(
+    date: &'static str,
+    account: Account<'static>,
+    num: impl Into<Decimal>,
+    currency: &'static str,
+) -> Directive<'static> {
+    Directive::Balance(
+        Balance::builder()
+            .date(Date::from_str_unchecked(date))
+            .account(account)
+            .amount(Amount::new(num.into(), currency))
+            .build(),
+    )
+}
@@ -9,19 +9,51 @@ use std::fmt;
 /// assert_eq!(Flag::default(), Flag::Okay);
 /// assert_eq!(Flag::from("*"), Flag::Okay);
 /// assert_eq!(Flag::from("!"), Flag::Warning);
+/// assert_eq!(Flag::from("P"), Flag::Padding);
 /// assert_eq!(Flag::from(":)"), Flag::Other(":)".into()));
 /// ```
 // TODO: Make sure that the variant Other("*") can't be created, since Other("*") != Okay
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Flag<'a> {
+    #[default]
     Okay,
     Warning,
+    /// `P`, a transaction inserted by a `pad` directive.
+    Padding,
+    /// `S`, a transaction summarizing past entries.
+    Summarize,
+    /// `T`, a transfer of balances between accounts.
+    Transfer,
+    /// `C`, a transaction converting between currencies.
+    Conversions,
+    /// `U`, a transaction recording unrealized gains.
+    Unrealized,
+    /// `R`, a transaction recording returns.
+    Returns,
+    /// `M`, a posting merging lots together for average cost.
+    Merging,
+    /// `#`, a forecasted transaction.
+    Forecasted,
     Other(Cow<'a, str>),
 }
 
-impl Default for Flag<'_> {
-    fn default() -> Self {
-        Flag::Okay
+impl Flag<'_> {
+    /// Copies the flag so that it no longer borrows the parsed input.
+    pub fn into_owned(self) -> Flag<'static> {
+        match self {
+            Flag::Okay => Flag::Okay,
+            Flag::Warning => Flag::Warning,
+            Flag::Padding => Flag::Padding,
+            Flag::Summarize => Flag::Summarize,
+            Flag::Transfer => Flag::Transfer,
+            Flag::Conversions => Flag::Conversions,
+            Flag::Unrealized => Flag::Unrealized,
+            Flag::Returns => Flag::Returns,
+            Flag::Merging => Flag::Merging,
+            Flag::Forecasted => Flag::Forecasted,
+            Flag::Other(s) => Flag::Other(crate::owned(s)),
+        }
     }
 }
 
@@ -42,6 +74,14 @@ impl<'a> From<Cow<'a, str>> for Flag<'a> {
         match &*s {
             "*" | "txn" => Flag::Okay,
             "!" => Flag::Warning,
+            "P" => Flag::Padding,
+            "S" => Flag::Summarize,
+            "T" => Flag::Transfer,
+            "C" => Flag::Conversions,
+            "U" => Flag::Unrealized,
+            "R" => Flag::Returns,
+            "M" => Flag::Merging,
+            "#" => Flag::Forecasted,
             _ => Flag::Other(s),
         }
     }
@@ -52,7 +92,42 @@ impl fmt::Display for Flag<'_> {
         match self {
             Flag::Okay => write!(f, "*"),
             Flag::Warning => write!(f, "!"),
+            Flag::Padding => write!(f, "P"),
+            Flag::Summarize => write!(f, "S"),
+            Flag::Transfer => write!(f, "T"),
+            Flag::Conversions => write!(f, "C"),
+            Flag::Unrealized => write!(f, "U"),
+            Flag::Returns => write!(f, "R"),
+            Flag::Merging => write!(f, "M"),
+            Flag::Forecasted => write!(f, "#"),
             Flag::Other(s) => write!(f, "{}", s),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let flags = [
+            ("*", Flag::Okay),
+            ("!", Flag::Warning),
+            ("P", Flag::Padding),
+            ("S", Flag::Summarize),
+            ("T", Flag::Transfer),
+            ("C", Flag::Conversions),
+            ("U", Flag::Unrealized),
+            ("R", Flag::Returns),
+            ("M", Flag::Merging),
+            ("#", Flag::Forecasted),
+            ("?", Flag::Other("?".into())),
+        ];
+        for (text, flag) in flags.iter() {
+            assert_eq!(&Flag::from(*text), flag);
+            assert_eq!(&flag.to_string(), text);
+        }
+        assert_eq!(Flag::from("txn"), Flag::Okay);
+    }
+}
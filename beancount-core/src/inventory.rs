@@ -0,0 +1,499 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+use super::amount::Amount;
+use super::directives::Booking;
+use super::position::{Cost, CostSpec, Position};
+use super::posting::{Posting, PriceSpec};
+use super::{Currency, Date};
+
+/// The positions held in an account, with positions held at cost kept as separate lots.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Inventory<'a> {
+    positions: Vec<Position<'a>>,
+}
+
+/// An error returned when a reducing posting can't be booked against an
+/// [`Inventory`](struct.Inventory.html).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BookingError {
+    /// The posting has no number or no commodity.
+    IncompletePosting,
+    /// The posting has zero units, so no per-unit cost or price can be derived from it.
+    ZeroUnits,
+    /// No lot held at cost matches the posting's cost.
+    NoMatchingLot,
+    /// Several lots match the posting's cost and the booking method can't choose between them.
+    AmbiguousMatch,
+    /// The matching lots hold fewer units than the posting reduces.
+    InsufficientUnits,
+    /// Reductions are not matched against lots under this booking method.
+    UnsupportedBooking(Booking),
+}
+
+impl fmt::Display for BookingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookingError::IncompletePosting => write!(f, "posting amount is incomplete"),
+            BookingError::ZeroUnits => write!(f, "posting has zero units"),
+            BookingError::NoMatchingLot => write!(f, "no lot matches the posting's cost"),
+            BookingError::AmbiguousMatch => {
+                write!(f, "several lots match the posting's cost ambiguously")
+            }
+            BookingError::InsufficientUnits => {
+                write!(f, "matching lots hold fewer units than the posting reduces")
+            }
+            BookingError::UnsupportedBooking(booking) => {
+                write!(f, "reductions are not supported with {:?} booking", booking)
+            }
+        }
+    }
+}
+
+impl error::Error for BookingError {}
+
+impl<'a> Inventory<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The positions in the inventory, in the order they were added.
+    pub fn positions(&self) -> &[Position<'a>] {
+        &self.positions
+    }
+
+    /// Adds a position to the inventory, merging it into an existing position with the same
+    /// commodity and cost. Positions that cancel out are removed.
+    pub fn add_position(&mut self, position: Position<'a>) {
+        let existing = self
+            .positions
+            .iter_mut()
+            .position(|p| p.units.currency == position.units.currency && p.cost == position.cost);
+        match existing {
+            Some(i) => {
+                self.positions[i].units.num += position.units.num;
+                self.positions[i].units.raw = None;
+                if self.positions[i].units.num.is_zero() {
+                    self.positions.remove(i);
+                }
+            }
+            None => self.positions.push(position),
+        }
+    }
+
+    /// Adds an amount held without cost, merging it into the other units of its commodity.
+    pub fn add_amount(&mut self, amount: Amount<'a>) {
+        self.add_position(Position::builder().units(amount).cost(None).build());
+    }
+
+    /// The net number of units held of each commodity, summing lots held at cost with units held
+    /// without cost. Sorted by commodity.
+    pub fn units(&self) -> Vec<Amount<'a>> {
+        let mut units: Vec<Amount<'a>> = Vec::new();
+        for position in &self.positions {
+            match units
+                .iter_mut()
+                .find(|amount| amount.currency == position.units.currency)
+            {
+                Some(amount) => amount.num += position.units.num,
+                None => units.push(position.units.clone()),
+            }
+        }
+        units.retain(|amount| !amount.num.is_zero());
+        units.sort_by(|a, b| a.currency.cmp(&b.currency));
+        units
+    }
+
+    /// Books a posting of a transaction dated `date` and returns the gains realized by it, one
+    /// amount per cost commodity.
+    ///
+    /// A posting held at cost whose units have the same sign as the lots of its commodity, such
+    /// as `10 HOOL {500 USD}` in an inventory without short lots, adds a lot under every booking
+    /// method. The lot is dated with the cost's date if it has one and `date` otherwise, and its
+    /// cost requires a number and commodity. Other postings held at cost are reductions, booked
+    /// with [`book_sale`](#method.book_sale); under `NONE` booking every posting held at cost adds
+    /// a lot. Postings without a cost are added with
+    /// [`add_amount`](#method.add_amount). The inventory is left unchanged if an error is
+    /// returned.
+    pub fn book_posting(
+        &mut self,
+        posting: &Posting<'a>,
+        date: &Date<'a>,
+        booking: &Booking,
+    ) -> Result<Vec<Amount<'a>>, BookingError> {
+        let (num, currency) = match (posting.units.num, &posting.units.currency) {
+            (Some(num), Some(currency)) => (num, currency),
+            _ => return Err(BookingError::IncompletePosting),
+        };
+        if num.is_zero() {
+            return Err(BookingError::ZeroUnits);
+        }
+        let spec = match &posting.cost {
+            Some(spec) => spec,
+            None => {
+                self.add_amount(Amount::new(num, currency.clone()));
+                return Ok(Vec::new());
+            }
+        };
+        let reduces = *booking != Booking::None
+            && self.positions.iter().any(|position| {
+                position.units.currency == *currency
+                    && position.cost.is_some()
+                    && position.units.num.is_sign_negative() != num.is_sign_negative()
+            });
+        if reduces {
+            return self.book_sale(posting, booking);
+        }
+        let cost = lot_cost(spec, num, Some(date)).ok_or(BookingError::IncompletePosting)?;
+        self.add_position(
+            Position::builder()
+                .units(Amount::new(num, currency.clone()))
+                .cost(Some(cost))
+                .build(),
+        );
+        Ok(Vec::new())
+    }
+
+    /// Books a posting that reduces lots held at cost, such as `-5 HOOL {500 USD} @ 600 USD`,
+    /// and returns the gains realized by it, one amount per cost commodity.
+    ///
+    /// Lots are matched against the posting's cost spec and reduced in the order given by
+    /// `booking`: oldest first for `FIFO`, newest first for `LIFO`. `STRICT` booking rejects
+    /// the posting if several lots match and it doesn't close all of them, and `STRICT_WITH_SIZE`
+    /// additionally accepts the oldest lot whose size matches exactly. `NONE` booking doesn't
+    /// match lots at all: the posting is added as a lot of its own, which requires its cost to
+    /// have a number, commodity and date, and realizes no gains. `AVERAGE` booking is not
+    /// supported.
+    ///
+    /// The gains are computed from the posting's price; a posting without a price, or with a
+    /// price in another commodity than the lot's cost, realizes no gain. The inventory is left
+    /// unchanged if an error is returned.
+    pub fn book_sale(
+        &mut self,
+        posting: &Posting<'a>,
+        booking: &Booking,
+    ) -> Result<Vec<Amount<'a>>, BookingError> {
+        if *booking == Booking::Average {
+            return Err(BookingError::UnsupportedBooking(booking.clone()));
+        }
+        let (num, currency) = match (posting.units.num, &posting.units.currency) {
+            (Some(num), Some(currency)) => (num, currency),
+            _ => return Err(BookingError::IncompletePosting),
+        };
+        if num.is_zero() {
+            return Err(BookingError::ZeroUnits);
+        }
+        let empty_spec = CostSpec::builder().build();
+        let spec = posting.cost.as_ref().unwrap_or(&empty_spec);
+        if *booking == Booking::None {
+            let cost = match &posting.cost {
+                Some(spec) => {
+                    Some(lot_cost(spec, num, None).ok_or(BookingError::IncompletePosting)?)
+                }
+                None => None,
+            };
+            self.add_position(
+                Position::builder()
+                    .units(Amount::new(num, currency.clone()))
+                    .cost(cost)
+                    .build(),
+            );
+            return Ok(Vec::new());
+        }
+
+        let mut candidates: Vec<usize> = (0..self.positions.len())
+            .filter(|&i| {
+                let position = &self.positions[i];
+                position.units.currency == *currency
+                    && position.units.num.is_sign_negative() != num.is_sign_negative()
+                    && position.cost.as_ref().is_some_and(|cost| {
+                        spec.number_per.is_none_or(|n| n == cost.number)
+                            && spec.currency.as_ref().is_none_or(|c| *c == cost.currency)
+                            && spec.date.as_ref().is_none_or(|d| *d == cost.date)
+                            && spec
+                                .label
+                                .as_ref()
+                                .is_none_or(|l| Some(l) == cost.label.as_ref())
+                    })
+            })
+            .collect();
+        if candidates.is_empty() {
+            return Err(BookingError::NoMatchingLot);
+        }
+        let reduction = num.abs();
+        let available: Decimal = candidates
+            .iter()
+            .map(|&i| self.positions[i].units.num.abs())
+            .sum();
+        if available < reduction {
+            return Err(BookingError::InsufficientUnits);
+        }
+        // Stable sorts, so lots with the same date stay in the order they were added.
+        let lot_date = |i: &usize| self.positions[*i].cost.as_ref().map(|c| c.date.clone());
+        match booking {
+            Booking::Lifo => {
+                candidates.reverse();
+                candidates.sort_by_key(|i| std::cmp::Reverse(lot_date(i)));
+            }
+            _ => candidates.sort_by_key(lot_date),
+        }
+        if candidates.len() > 1 && available != reduction {
+            match booking {
+                Booking::Strict => return Err(BookingError::AmbiguousMatch),
+                Booking::StrictWithSize => {
+                    let exact = candidates
+                        .iter()
+                        .copied()
+                        .find(|&i| self.positions[i].units.num.abs() == reduction)
+                        .ok_or(BookingError::AmbiguousMatch)?;
+                    candidates = vec![exact];
+                }
+                _ => {}
+            }
+        }
+
+        let price = posting.price.as_ref().and_then(|price| match price {
+            PriceSpec::PerUnit(amount) => Some((amount.num?, amount.currency.as_ref()?)),
+            PriceSpec::Total(amount) => Some((amount.num? / reduction, amount.currency.as_ref()?)),
+        });
+        let sign = if num.is_sign_negative() {
+            -Decimal::ONE
+        } else {
+            Decimal::ONE
+        };
+        let mut gains: HashMap<Currency<'a>, Decimal> = HashMap::new();
+        let mut remaining = reduction;
+        for i in candidates {
+            if remaining.is_zero() {
+                break;
+            }
+            let position = &mut self.positions[i];
+            let taken = remaining.min(position.units.num.abs());
+            remaining -= taken;
+            position.units.num += taken * sign;
+            position.units.raw = None;
+            if let (Some((price, price_currency)), Some(cost)) = (price, &position.cost) {
+                if *price_currency == cost.currency {
+                    *gains.entry(cost.currency.clone()).or_default() +=
+                        -taken * (price - cost.number) * sign;
+                }
+            }
+        }
+        self.positions.retain(|p| !p.units.num.is_zero());
+
+        let mut gains: Vec<_> = gains
+            .into_iter()
+            .map(|(currency, num)| Amount::new(num, currency))
+            .collect();
+        gains.sort_by(|a, b| a.currency.cmp(&b.currency));
+        Ok(gains)
+    }
+}
+
+/// The cost of a lot of `num` units bought at `spec`, if the spec is complete. The lot is dated
+/// `date` if the spec has no date of its own. `num` must not be zero.
+fn lot_cost<'a>(spec: &CostSpec<'a>, num: Decimal, date: Option<&Date<'a>>) -> Option<Cost<'a>> {
+    let per_unit = match (spec.number_per, spec.number_total) {
+        (per, None) => per?,
+        (per, Some(total)) => per.unwrap_or_default() + total / num.abs(),
+    };
+    Some(
+        Cost::builder()
+            .number(per_unit)
+            .currency(spec.currency.clone()?)
+            .date(spec.date.clone().or_else(|| date.cloned())?)
+            .label(spec.label.clone())
+            .build(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{assets, posting};
+    use crate::*;
+
+    fn lot(num: i64, cost: i64, date: &'static str) -> Position<'static> {
+        Position::builder()
+            .units(Amount::new(num.into(), "HOOL"))
+            .cost(Some(
+                Cost::builder()
+                    .number(cost.into())
+                    .currency("USD".into())
+                    .date(Date::from_str_unchecked(date))
+                    .label(None)
+                    .build(),
+            ))
+            .build()
+    }
+
+    fn sale(num: i64, cost: Option<i64>, price: i64) -> Posting<'static> {
+        let mut posting = posting(assets("Broker"), num, "HOOL");
+        posting.cost = Some(
+            CostSpec::builder()
+                .number_per(cost.map(Into::into))
+                .currency(Some("USD".into()))
+                .build(),
+        );
+        posting.price = Some(PriceSpec::PerUnit(Amount::new(price.into(), "USD").into()));
+        posting
+    }
+
+    /// A posting buying `num` HOOL at `cost` USD each, with an undated cost like most parsed
+    /// postings.
+    fn buy(num: i64, cost: i64) -> Posting<'static> {
+        let mut posting = sale(num, Some(cost), cost);
+        posting.price = None;
+        posting
+    }
+
+    /// Two lots of 10 HOOL, bought at 500 USD on 2020-01-01 and at 520 USD on 2020-02-01.
+    fn inventory() -> Inventory<'static> {
+        let mut inventory = Inventory::new();
+        for (posting, date) in [(buy(10, 500), "2020-01-01"), (buy(10, 520), "2020-02-01")] {
+            let booked =
+                inventory.book_posting(&posting, &Date::from_str_unchecked(date), &Booking::Fifo);
+            assert_eq!(booked, Ok(vec![]));
+        }
+        inventory
+    }
+
+    #[test]
+    fn fifo_sale() {
+        let mut inventory = inventory();
+        assert_eq!(
+            inventory.positions(),
+            &[lot(10, 500, "2020-01-01"), lot(10, 520, "2020-02-01")]
+        );
+        let gains = inventory.book_posting(
+            &sale(-15, None, 600),
+            &Date::from_str_unchecked("2020-03-01"),
+            &Booking::Fifo,
+        );
+        assert_eq!(gains, Ok(vec![Amount::new(1400.into(), "USD")]));
+        assert_eq!(inventory.positions(), &[lot(5, 520, "2020-02-01")]);
+    }
+
+    #[test]
+    fn lifo_sale() {
+        let mut inventory = inventory();
+        let gains = inventory.book_sale(&sale(-15, None, 600), &Booking::Lifo);
+        assert_eq!(gains, Ok(vec![Amount::new(1300.into(), "USD")]));
+        assert_eq!(inventory.positions(), &[lot(5, 500, "2020-01-01")]);
+    }
+
+    #[test]
+    fn strict_sale() {
+        let mut inventory = inventory();
+        assert_eq!(
+            inventory.book_sale(&sale(-5, None, 600), &Booking::Strict),
+            Err(BookingError::AmbiguousMatch)
+        );
+        assert_eq!(inventory, self::inventory());
+        let gains = inventory.book_sale(&sale(-5, Some(500), 450), &Booking::Strict);
+        assert_eq!(gains, Ok(vec![Amount::new((-250).into(), "USD")]));
+        assert_eq!(
+            inventory.book_sale(&sale(-25, None, 600), &Booking::Strict),
+            Err(BookingError::InsufficientUnits)
+        );
+    }
+
+    #[test]
+    fn buys() {
+        let date = Date::from_str_unchecked;
+        for booking in [Booking::Fifo, Booking::Lifo, Booking::Strict] {
+            let mut inventory = Inventory::new();
+            let booked = inventory.book_posting(&buy(10, 500), &date("2020-01-01"), &booking);
+            assert_eq!(booked, Ok(vec![]));
+            assert_eq!(inventory.positions(), &[lot(10, 500, "2020-01-01")]);
+        }
+
+        // A buy without a cost number can't be added as a lot.
+        let mut inventory = inventory();
+        assert_eq!(
+            inventory.book_posting(&sale(1, None, 510), &date("2020-03-01"), &Booking::Fifo),
+            Err(BookingError::IncompletePosting)
+        );
+        assert_eq!(inventory, self::inventory());
+    }
+
+    #[test]
+    fn zero_units() {
+        let mut inventory = inventory();
+        let mut total_cost = buy(0, 0);
+        if let Some(spec) = &mut total_cost.cost {
+            spec.number_per = None;
+            spec.number_total = Some(100.into());
+        }
+        assert_eq!(
+            inventory.book_posting(
+                &total_cost,
+                &Date::from_str_unchecked("2020-03-01"),
+                &Booking::Fifo
+            ),
+            Err(BookingError::ZeroUnits)
+        );
+        assert_eq!(
+            inventory.book_sale(&total_cost, &Booking::None),
+            Err(BookingError::ZeroUnits)
+        );
+        let mut total_price = sale(0, None, 0);
+        total_price.price = Some(PriceSpec::Total(Amount::new(600.into(), "USD").into()));
+        assert_eq!(
+            inventory.book_sale(&total_price, &Booking::Fifo),
+            Err(BookingError::ZeroUnits)
+        );
+        assert_eq!(inventory, self::inventory());
+    }
+
+    #[test]
+    fn buys_and_sales() {
+        let date = Date::from_str_unchecked;
+        let cash = |num: i64| posting(assets("Cash"), num, "USD");
+        let mut inventory = Inventory::new();
+        for (posting, txn_date) in [
+            (cash(10000), "2020-01-01"),
+            (buy(10, 500), "2020-01-01"),
+            (cash(-5000), "2020-01-01"),
+            (buy(5, 520), "2020-02-01"),
+            (cash(-2600), "2020-02-01"),
+        ] {
+            let booked = inventory.book_posting(&posting, &date(txn_date), &Booking::Fifo);
+            assert_eq!(booked, Ok(vec![]));
+        }
+        assert_eq!(
+            inventory.positions(),
+            &[
+                Position::builder()
+                    .units(Amount::new(2400.into(), "USD"))
+                    .cost(None)
+                    .build(),
+                lot(10, 500, "2020-01-01"),
+                lot(5, 520, "2020-02-01"),
+            ]
+        );
+
+        let gains =
+            inventory.book_posting(&sale(-12, None, 510), &date("2020-03-01"), &Booking::Fifo);
+        assert_eq!(gains, Ok(vec![Amount::new(80.into(), "USD")]));
+        assert_eq!(
+            inventory.units(),
+            vec![
+                Amount::new(3.into(), "HOOL"),
+                Amount::new(2400.into(), "USD"),
+            ]
+        );
+
+        // Under NONE booking a sale is a lot of its own, so units still net out.
+        let sale = sale(-3, Some(520), 530);
+        assert_eq!(
+            inventory.book_posting(&sale, &date("2020-03-01"), &Booking::None),
+            Ok(vec![])
+        );
+        assert_eq!(inventory.positions().len(), 3);
+        assert_eq!(inventory.units(), vec![Amount::new(2400.into(), "USD")]);
+    }
+}
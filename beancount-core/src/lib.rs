@@ -1,32 +1,498 @@
 use std::borrow::Cow;
+use std::collections::HashSet;
 
 use typed_builder::TypedBuilder;
 
-pub use account::Account;
-pub use account_types::AccountType;
-pub use amount::{Amount, IncompleteAmount};
-pub use date::Date;
+pub use account::{Account, ParseAccountError};
+pub use account_types::{AccountType, ParseAccountTypeError};
+pub use amount::{Amount, AmountError, IncompleteAmount, ParseAmountError};
+pub use annotated::Annotated;
+pub use date::{Date, DateError};
 pub use directives::*;
 pub use flags::Flag;
+pub use inventory::Inventory;
 pub use position::CostSpec;
 pub use posting::Posting;
 pub use posting::PriceSpec;
+pub use reports::Period;
+pub use stats::LedgerStats;
 
 pub mod account;
 pub mod account_types;
 pub mod amount;
+pub mod annotated;
+pub mod assertions;
+pub mod balance;
 mod date;
 pub mod directives;
+#[cfg(test)]
+mod fixtures;
 pub mod flags;
+pub mod inventory;
 pub mod metadata;
+pub mod pads;
 pub mod position;
 pub mod posting;
+mod reports;
+pub mod rounding;
+pub mod stats;
+pub mod validation;
 
 /// Represents the complete ledger consisting of a number of directives.
 // TODO: Derive Hash when possible
 #[derive(Clone, Debug, PartialEq, Default, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ledger<'a> {
     pub directives: Vec<Directive<'a>>,
 }
 
+impl<'a> Ledger<'a> {
+    /// Iterates over the transactions in the ledger, in ledger order.
+    pub fn transactions(&self) -> impl Iterator<Item = &Transaction<'a>> {
+        self.directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Transaction(txn) => Some(txn),
+                _ => None,
+            })
+    }
+
+    /// Iterates over the `open` directives in the ledger, in ledger order.
+    pub fn opens(&self) -> impl Iterator<Item = &Open<'a>> {
+        self.directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Open(open) => Some(open),
+                _ => None,
+            })
+    }
+
+    /// Iterates over the `balance` directives in the ledger, in ledger order.
+    pub fn balances(&self) -> impl Iterator<Item = &Balance<'a>> {
+        self.directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Balance(balance) => Some(balance),
+                _ => None,
+            })
+    }
+
+    /// Iterates over the `price` directives in the ledger, in ledger order.
+    pub fn prices(&self) -> impl Iterator<Item = &Price<'a>> {
+        self.directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Price(price) => Some(price),
+                _ => None,
+            })
+    }
+
+    /// The currencies declared with `option "operating_currency"`, in the order they are declared.
+    /// A currency declared more than once is listed once.
+    pub fn operating_currencies(&self) -> Vec<Currency<'a>> {
+        let mut currencies: Vec<Currency<'a>> = Vec::new();
+        for directive in &self.directives {
+            if let Directive::Option(option) = directive {
+                if let Some(KnownOption::OperatingCurrency(currency)) = option.parsed() {
+                    if !currencies.contains(&currency) {
+                        currencies.push(currency);
+                    }
+                }
+            }
+        }
+        currencies
+    }
+
+    /// Iterates over every posting in the ledger together with the transaction it belongs to.
+    pub fn postings(&self) -> impl Iterator<Item = (&Transaction<'a>, &Posting<'a>)> {
+        self.transactions()
+            .flat_map(|txn| txn.postings.iter().map(move |posting| (txn, posting)))
+    }
+
+    /// Transactions carrying `link`, sorted by date. Transactions on the same date keep their
+    /// order in the ledger.
+    pub fn linked(&self, link: &metadata::Link<'_>) -> Vec<&Transaction<'a>> {
+        let mut linked: Vec<_> = self
+            .directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Transaction(txn) if txn.links.contains(link) => Some(txn),
+                _ => None,
+            })
+            .collect();
+        linked.sort_by_key(|txn| &txn.date);
+        linked
+    }
+
+    /// Stably sorts the directives by [date](enum.Directive.html#method.date). Dateless
+    /// directives such as `option` and `plugin` are moved to the front, keeping their relative
+    /// order, and directives on the same date keep theirs.
+    pub fn sort_by_date(&mut self) {
+        self.directives.sort_by(|a, b| a.date().cmp(&b.date()));
+    }
+
+    /// Replaces every empty or whitespace-only transaction payee with `None`, so a transaction
+    /// written as `"" "Narration"` renders with a single string.
+    pub fn drop_empty_payees(&mut self) {
+        for directive in &mut self.directives {
+            if let Directive::Transaction(txn) = directive {
+                if txn
+                    .payee
+                    .as_deref()
+                    .is_some_and(|payee| payee.trim().is_empty())
+                {
+                    txn.payee = None;
+                }
+            }
+        }
+    }
+
+    /// Copies every borrowed string in the ledger so that it no longer borrows the parsed input.
+    /// The `source` of every directive is set to `None`, since it can only borrow the input.
+    pub fn into_owned(self) -> Ledger<'static> {
+        Ledger {
+            directives: self
+                .directives
+                .into_iter()
+                .map(Directive::into_owned)
+                .collect(),
+        }
+    }
+
+    /// Sets the `source` of every directive to `None`, so the ledger no longer borrows the
+    /// parsed input through them.
+    pub fn strip_sources(&mut self) {
+        for directive in &mut self.directives {
+            let source = match directive {
+                Directive::Open(d) => &mut d.source,
+                Directive::Close(d) => &mut d.source,
+                Directive::Balance(d) => &mut d.source,
+                Directive::Option(d) => &mut d.source,
+                Directive::Commodity(d) => &mut d.source,
+                Directive::Custom(d) => &mut d.source,
+                Directive::Document(d) => &mut d.source,
+                Directive::Event(d) => &mut d.source,
+                Directive::Include(d) => &mut d.source,
+                Directive::Note(d) => &mut d.source,
+                Directive::Pad(d) => &mut d.source,
+                Directive::Plugin(d) => &mut d.source,
+                Directive::Price(d) => &mut d.source,
+                Directive::Query(d) => &mut d.source,
+                Directive::Transaction(d) => &mut d.source,
+                Directive::Unsupported(_) => continue,
+            };
+            *source = None;
+        }
+    }
+}
+
 pub type Currency<'a> = Cow<'a, str>;
+
+/// Copies a possibly borrowed string so that it no longer borrows the input.
+pub(crate) fn owned(s: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(s.into_owned())
+}
+
+/// Copies a set of possibly borrowed strings, such as tags or links, so that it no longer
+/// borrows the input.
+pub(crate) fn owned_set(set: HashSet<Cow<'_, str>>) -> HashSet<Cow<'static, str>> {
+    set.into_iter().map(owned).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger(source: Option<&str>) -> Ledger<'_> {
+        let account = Account::builder()
+            .ty(AccountType::Assets)
+            .parts(vec!["Cash".into()])
+            .build();
+        Ledger::builder()
+            .directives(vec![
+                Directive::Open(
+                    Open::builder()
+                        .date(Date::from_str_unchecked("2020-01-01"))
+                        .account(account.clone())
+                        .source(source)
+                        .build(),
+                ),
+                Directive::Close(
+                    Close::builder()
+                        .date(Date::from_str_unchecked("2020-12-31"))
+                        .account(account)
+                        .source(source)
+                        .build(),
+                ),
+                Directive::Unsupported("* Cash\n".into()),
+            ])
+            .build()
+    }
+
+    fn transaction(
+        date: &'static str,
+        narration: &'static str,
+        links: &[&'static str],
+    ) -> Directive<'static> {
+        Directive::Transaction(
+            Transaction::builder()
+                .date(Date::from_str_unchecked(date))
+                .narration(narration.into())
+                .links(links.iter().map(|&link| link.into()).collect())
+                .build(),
+        )
+    }
+
+    #[test]
+    fn directives_by_type() {
+        let price = |date: &'static str| {
+            Directive::Price(
+                Price::builder()
+                    .date(Date::from_str_unchecked(date))
+                    .currency("HOOL".into())
+                    .amount(Amount::new(10.into(), "USD"))
+                    .build(),
+            )
+        };
+        let mut ledger = ledger(None);
+        ledger.directives.extend(vec![
+            transaction("2020-02-01", "First", &[]),
+            price("2020-02-02"),
+            transaction("2020-02-03", "Second", &[]),
+            price("2020-02-04"),
+            Directive::Balance(
+                Balance::builder()
+                    .date(Date::from_str_unchecked("2020-02-05"))
+                    .account(
+                        Account::builder()
+                            .ty(AccountType::Assets)
+                            .parts(vec!["Cash".into()])
+                            .build(),
+                    )
+                    .amount(Amount::new(0.into(), "USD"))
+                    .build(),
+            ),
+        ]);
+
+        let narrations: Vec<_> = ledger
+            .transactions()
+            .map(|txn| txn.narration.as_ref())
+            .collect();
+        assert_eq!(narrations, vec!["First", "Second"]);
+        assert_eq!(ledger.opens().count(), 1);
+        assert_eq!(ledger.balances().count(), 1);
+        let price_dates: Vec<_> = ledger
+            .prices()
+            .map(|price| price.date.to_string())
+            .collect();
+        assert_eq!(price_dates, vec!["2020-02-02", "2020-02-04"]);
+    }
+
+    #[test]
+    fn operating_currencies() {
+        let option = |name: &'static str, val: &'static str| {
+            Directive::Option(
+                BcOption::builder()
+                    .name(name.into())
+                    .val(val.into())
+                    .build(),
+            )
+        };
+        let ledger = Ledger::builder()
+            .directives(vec![
+                option("title", "Books"),
+                option("operating_currency", "USD"),
+                transaction("2020-01-01", "January", &[]),
+                option("operating_currency", "CAD"),
+                option("operating_currency", "USD"),
+            ])
+            .build();
+        assert_eq!(ledger.operating_currencies(), vec!["USD", "CAD"]);
+        assert!(Ledger::default().operating_currencies().is_empty());
+    }
+
+    #[test]
+    fn sort_by_date() {
+        let option = |name: &'static str| {
+            Directive::Option(
+                BcOption::builder()
+                    .name(name.into())
+                    .val("value".into())
+                    .build(),
+            )
+        };
+        let mut ledger = Ledger::builder()
+            .directives(vec![
+                transaction("2020-03-01", "March", &[]),
+                option("title"),
+                transaction("2020/01/15", "January", &[]),
+                transaction("2020-03-01", "Also March", &[]),
+                option("operating_currency"),
+                transaction("2020-02-01", "February", &[]),
+            ])
+            .build();
+        ledger.sort_by_date();
+
+        let order: Vec<_> = ledger
+            .directives
+            .iter()
+            .map(|directive| match directive {
+                Directive::Transaction(txn) => txn.narration.as_ref(),
+                Directive::Option(option) => option.name.as_ref(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                "title",
+                "operating_currency",
+                "January",
+                "February",
+                "March",
+                "Also March"
+            ]
+        );
+    }
+
+    #[test]
+    fn linked() {
+        let ledger = Ledger::builder()
+            .directives(vec![
+                transaction("2014-02-20", "Check deposit", &["invoice-42"]),
+                transaction("2014-02-10", "Unrelated", &["invoice-43"]),
+                transaction("2014-02-05", "Invoice for January", &["invoice-42", "jan"]),
+            ])
+            .build();
+        let narrations: Vec<_> = ledger
+            .linked(&"invoice-42".into())
+            .iter()
+            .map(|txn| txn.narration.as_ref())
+            .collect();
+        assert_eq!(narrations, vec!["Invoice for January", "Check deposit"]);
+        assert!(ledger.linked(&"invoice-44".into()).is_empty());
+    }
+
+    #[test]
+    fn drop_empty_payees() {
+        let with_payee = |payee: &'static str| {
+            Directive::Transaction(
+                Transaction::builder()
+                    .date(Date::from_str_unchecked("2020-01-01"))
+                    .payee(Some(payee.into()))
+                    .narration("Narration".into())
+                    .build(),
+            )
+        };
+        let mut ledger = Ledger::builder()
+            .directives(vec![with_payee(""), with_payee(" \t"), with_payee("Cafe")])
+            .build();
+        ledger.drop_empty_payees();
+        let payees: Vec<_> = ledger
+            .directives
+            .iter()
+            .map(|directive| match directive {
+                Directive::Transaction(txn) => txn.payee.as_deref(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(payees, vec![None, None, Some("Cafe")]);
+    }
+
+    #[test]
+    fn strip_sources() {
+        let input = String::from("2020-01-01 open Assets:Cash");
+        let mut stripped = ledger(Some(&input));
+        assert_ne!(stripped, ledger(None));
+        stripped.strip_sources();
+        assert_eq!(stripped, ledger(None));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use metadata::MetaValue;
+        use rust_decimal::Decimal;
+
+        let account =
+            |ty, name: &'static str| Account::builder().ty(ty).parts(vec![name.into()]).build();
+        let meta: metadata::Meta<'_> = vec![
+            ("note".into(), MetaValue::Text("quoted \"text\"".into())),
+            ("count".into(), MetaValue::Number(Decimal::new(12345, 4))),
+            ("paid".into(), MetaValue::Bool(true)),
+            (
+                "fee".into(),
+                MetaValue::Amount(Amount::new(Decimal::new(150, 2), "USD")),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let ledger = Ledger::builder()
+            .directives(vec![
+                Directive::Open(
+                    Open::builder()
+                        .date(Date::from_str_unchecked("2020-01-01"))
+                        .account(account(AccountType::Assets, "Broker"))
+                        .currencies(vec!["HOOL".into(), "USD".into()])
+                        .booking(Some(Booking::Fifo))
+                        .build(),
+                ),
+                Directive::Transaction(
+                    Transaction::builder()
+                        .date(Date::from_str_unchecked("2020/01/02"))
+                        .flag(Flag::Padding)
+                        .payee(Some("Broker".into()))
+                        .narration("Buy".into())
+                        .tags(vec!["trading".into()].into_iter().collect())
+                        .meta(meta)
+                        .postings(vec![
+                            Posting::builder()
+                                .account(account(AccountType::Assets, "Broker"))
+                                .units(
+                                    IncompleteAmount::builder()
+                                        .num(Some(Decimal::new(10, 0)))
+                                        .currency(Some("HOOL".into()))
+                                        .build(),
+                                )
+                                .cost(Some(
+                                    CostSpec::builder()
+                                        .number_per(Some(Decimal::new(5001, 2)))
+                                        .currency(Some("USD".into()))
+                                        .build(),
+                                ))
+                                .price(Some(PriceSpec::PerUnit(
+                                    IncompleteAmount::builder()
+                                        .num(Some(Decimal::new(51, 0)))
+                                        .currency(Some("USD".into()))
+                                        .build(),
+                                )))
+                                .build(),
+                            Posting::builder()
+                                .flag(Some(Flag::Warning))
+                                .account(account(AccountType::Assets, "Cash"))
+                                .units(IncompleteAmount::builder().build())
+                                .build(),
+                        ])
+                        .build(),
+                ),
+                Directive::Balance(
+                    Balance::builder()
+                        .date(Date::from_str_unchecked("2020-01-03"))
+                        .account(account(AccountType::Assets, "Broker"))
+                        .amount(Amount::new(Decimal::new(10, 0), "HOOL"))
+                        .tolerance(Some(Decimal::new(1, 3)))
+                        .build(),
+                ),
+                Directive::Unsupported("* Cash\n".into()),
+            ])
+            .build();
+
+        let json = serde_json::to_string(&ledger).unwrap();
+        // Decimals are written as strings so no precision is lost.
+        assert!(json.contains("\"50.01\""));
+        // Dates are written as their text.
+        assert!(json.contains("\"2020/01/02\""));
+        let deserialized: Ledger<'_> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, ledger);
+    }
+}
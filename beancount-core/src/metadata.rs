@@ -1,5 +1,6 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::{error, fmt};
 
 use rust_decimal::Decimal;
 
@@ -7,8 +8,8 @@ use rust_decimal::Decimal;
 pub type Meta<'a> = HashMap<Cow<'a, str>, MetaValue<'a>>;
 
 /// An enum of the valid values in a metadata map.
-// TODO: Implement Display
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MetaValue<'a> {
     Text(Cow<'a, str>),
     Account(super::account::Account<'a>),
@@ -20,6 +21,90 @@ pub enum MetaValue<'a> {
     Number(Decimal),
 }
 
+impl MetaValue<'_> {
+    /// Copies the value so that it no longer borrows the parsed input.
+    pub fn into_owned(self) -> MetaValue<'static> {
+        match self {
+            MetaValue::Text(text) => MetaValue::Text(crate::owned(text)),
+            MetaValue::Account(account) => MetaValue::Account(account.into_owned()),
+            MetaValue::Date(date) => MetaValue::Date(date.into_owned()),
+            MetaValue::Currency(currency) => MetaValue::Currency(crate::owned(currency)),
+            MetaValue::Tag(tag) => MetaValue::Tag(crate::owned(tag)),
+            MetaValue::Bool(b) => MetaValue::Bool(b),
+            MetaValue::Amount(amount) => MetaValue::Amount(amount.into_owned()),
+            MetaValue::Number(num) => MetaValue::Number(num),
+        }
+    }
+}
+
+/// Copies metadata so that it no longer borrows the parsed input.
+pub(crate) fn owned_meta(meta: Meta<'_>) -> Meta<'static> {
+    meta.into_iter()
+        .map(|(key, value)| (crate::owned(key), value.into_owned()))
+        .collect()
+}
+
+/// Formats the value as it is written in beancount: text quoted, accounts as `Type:Part:Part`,
+/// tags with a leading `#`, booleans as `true`/`false`, and numbers in plain decimal notation.
+impl fmt::Display for MetaValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetaValue::Text(text) => write!(f, "\"{}\"", text),
+            MetaValue::Account(account) => write!(f, "{}", account),
+            MetaValue::Date(date) => write!(f, "{}", date),
+            MetaValue::Currency(currency) => write!(f, "{}", currency),
+            MetaValue::Tag(tag) => write!(f, "#{}", tag),
+            MetaValue::Bool(b) => write!(f, "{}", b),
+            MetaValue::Amount(amount) => write!(f, "{} {}", amount.num, amount.currency),
+            MetaValue::Number(num) => write!(f, "{}", num),
+        }
+    }
+}
+
+/// Error returned by [`validate_meta_key`](fn.validate_meta_key.html) for a key that can't be
+/// written as beancount metadata.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MetaKeyError {
+    /// The offending key.
+    pub key: String,
+}
+
+impl fmt::Display for MetaKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid metadata key '{}': keys must start with a lowercase letter followed by at \
+             least one letter, digit, '-' or '_'",
+            self.key
+        )
+    }
+}
+
+impl error::Error for MetaKeyError {}
+
+/// Checks that `key` is a valid metadata key: a lowercase ASCII letter followed by one or more
+/// ASCII letters, digits, dashes or underscores.
+///
+/// # Example
+/// ```rust
+/// use beancount_core::metadata::validate_meta_key;
+/// assert!(validate_meta_key("foo-bar").is_ok());
+/// assert!(validate_meta_key("Foo").is_err());
+/// ```
+pub fn validate_meta_key(key: &str) -> Result<(), MetaKeyError> {
+    let mut chars = key.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && key.len() > 1
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(MetaKeyError {
+            key: key.to_string(),
+        })
+    }
+}
+
 /// Tag associated with a transaction directive.  Tags allow you to mark a subset of transactions,
 /// enabling filtering on a tag(s) when generating a report.
 ///
@@ -55,3 +140,60 @@ pub type Tag<'a> = Cow<'a, str>;
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.k4v5vkjukel7>
 pub type Link<'a> = Cow<'a, str>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::account;
+    use crate::{AccountType, Amount, Date};
+
+    #[test]
+    fn display_meta_values() {
+        assert_eq!(MetaValue::Text("hello".into()).to_string(), "\"hello\"");
+        assert_eq!(
+            MetaValue::Account(account(
+                AccountType::Liabilities,
+                &["CreditCard", "CapitalOne"]
+            ))
+            .to_string(),
+            "Liabilities:CreditCard:CapitalOne"
+        );
+        assert_eq!(
+            MetaValue::Date(Date::from_str_unchecked("2020-01-01")).to_string(),
+            "2020-01-01"
+        );
+        assert_eq!(MetaValue::Currency("USD".into()).to_string(), "USD");
+        assert_eq!(MetaValue::Tag("trip".into()).to_string(), "#trip");
+        assert_eq!(MetaValue::Bool(true).to_string(), "true");
+        assert_eq!(MetaValue::Bool(false).to_string(), "false");
+        assert_eq!(
+            MetaValue::Amount(Amount::new(Decimal::new(-20050, 2), "USD")).to_string(),
+            "-200.50 USD"
+        );
+        assert_eq!(
+            MetaValue::Number(Decimal::new(1, 20)).to_string(),
+            "0.00000000000000000001"
+        );
+        assert_eq!(
+            MetaValue::Number(Decimal::new(12, 0) * Decimal::new(10i64.pow(18), 0)).to_string(),
+            "12000000000000000000"
+        );
+    }
+
+    #[test]
+    fn meta_keys() {
+        assert!(validate_meta_key("foo-bar").is_ok());
+        assert!(validate_meta_key("key1").is_ok());
+        assert!(validate_meta_key("snake_case").is_ok());
+
+        assert!(validate_meta_key("Foo").is_err());
+        assert!(validate_meta_key("k").is_err());
+        assert!(validate_meta_key("").is_err());
+        assert!(validate_meta_key("foo bar").is_err());
+        assert!(validate_meta_key("1key").is_err());
+        assert_eq!(
+            validate_meta_key("Foo"),
+            Err(MetaKeyError { key: "Foo".into() })
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use rust_decimal::Decimal;
+use typed_builder::TypedBuilder;
+
+use super::account::Account;
+use super::amount::{Amount, IncompleteAmount};
+use super::directives::{Directive, Transaction};
+use super::flags::Flag;
+use super::posting::Posting;
+use super::{Currency, Ledger};
+
+/// Options for the transactions generated by [`apply_pads`](fn.apply_pads.html).
+#[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+pub struct PadOptions<'a> {
+    /// Flag of the generated transactions.
+    #[builder(default = Flag::Padding)]
+    pub flag: Flag<'a>,
+
+    /// Narration of the generated transactions. When `None`, beancount's
+    /// `(Padding inserted for Balance of ... for difference ...)` text is used.
+    #[builder(default)]
+    pub narration: Option<Cow<'a, str>>,
+}
+
+impl Default for PadOptions<'_> {
+    fn default() -> Self {
+        PadOptions::builder().build()
+    }
+}
+
+/// Returns a copy of the ledger with a padding transaction inserted after every `pad` directive
+/// that is followed by a failing `balance` directive on the padded account.
+///
+/// Each generated transaction is dated on the pad and posts the difference between the asserted
+/// and actual balance to the padded account, balanced against the pad's source account. A pad is
+/// applied at most once per commodity; pads that are never followed by a balance directive for
+/// their account are left as they are. Like in
+/// [`verify_balances`](../assertions/fn.verify_balances.html), elided posting amounts are inferred
+/// with [`Transaction::solved_units`](../struct.Transaction.html#method.solved_units).
+pub fn apply_pads<'a>(ledger: &Ledger<'a>, options: &PadOptions<'a>) -> Ledger<'a> {
+    let mut order: Vec<usize> = (0..ledger.directives.len())
+        .filter(|&i| ledger.directives[i].date().is_some())
+        .collect();
+    order.sort_by_key(|&i| {
+        let directive = &ledger.directives[i];
+        (
+            directive.date(),
+            !matches!(directive, Directive::Balance(_)),
+        )
+    });
+
+    let mut balances: HashMap<(&Account<'a>, Currency<'a>), Decimal> = HashMap::new();
+    // Latest pad for each account, with the commodities it has already been applied to.
+    let mut pending: HashMap<&Account<'a>, (usize, HashSet<&str>)> = HashMap::new();
+    let mut inserted: HashMap<usize, Vec<Transaction<'a>>> = HashMap::new();
+    for i in order {
+        match &ledger.directives[i] {
+            Directive::Transaction(txn) => {
+                for (posting, units) in txn.solved_units() {
+                    *balances
+                        .entry((&posting.account, units.currency))
+                        .or_default() += units.num;
+                }
+            }
+            Directive::Pad(pad) => {
+                pending.insert(&pad.pad_to_account, (i, HashSet::new()));
+            }
+            Directive::Balance(balance) => {
+                let currency = balance.amount.currency.as_ref();
+                let pad_index = match pending.get_mut(&balance.account) {
+                    Some((pad_index, padded)) => {
+                        if !padded.insert(currency) {
+                            continue;
+                        }
+                        *pad_index
+                    }
+                    None => continue,
+                };
+                let actual = balances
+                    .get(&(&balance.account, balance.amount.currency.clone()))
+                    .copied()
+                    .unwrap_or_default();
+                let difference = balance.amount.num - actual;
+                if difference.abs() <= balance.tolerance.unwrap_or_default() {
+                    continue;
+                }
+                let pad = match &ledger.directives[pad_index] {
+                    Directive::Pad(pad) => pad,
+                    _ => unreachable!(),
+                };
+                let padded_currency = balance.amount.currency.clone();
+                *balances
+                    .entry((&pad.pad_to_account, padded_currency.clone()))
+                    .or_default() += difference;
+                *balances
+                    .entry((&pad.pad_from_account, padded_currency))
+                    .or_default() -= difference;
+
+                let narration = options.narration.clone().unwrap_or_else(|| {
+                    format!(
+                        "(Padding inserted for Balance of {} {} for difference {} {})",
+                        balance.amount.num, currency, difference, currency
+                    )
+                    .into()
+                });
+                let posting = |account: &Account<'a>, num: Decimal| {
+                    Posting::builder()
+                        .account(account.clone())
+                        .units(IncompleteAmount::from(Amount::new(
+                            num,
+                            balance.amount.currency.clone(),
+                        )))
+                        .build()
+                };
+                inserted.entry(pad_index).or_default().push(
+                    Transaction::builder()
+                        .date(pad.date.clone())
+                        .flag(options.flag.clone())
+                        .narration(narration)
+                        .postings(vec![
+                            posting(&pad.pad_to_account, difference),
+                            posting(&pad.pad_from_account, -difference),
+                        ])
+                        .build(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let mut directives = Vec::with_capacity(ledger.directives.len() + inserted.len());
+    for (i, directive) in ledger.directives.iter().enumerate() {
+        directives.push(directive.clone());
+        if let Some(txns) = inserted.remove(&i) {
+            directives.extend(txns.into_iter().map(Directive::Transaction));
+        }
+    }
+    Ledger { directives }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assertions::verify_balances;
+    use crate::fixtures::{account, balance, elided, posting, transaction};
+    use crate::*;
+
+    fn ledger() -> Ledger<'static> {
+        let checking = account(AccountType::Assets, &["BofA", "Checking"]);
+        let opening = account(AccountType::Equity, &["Opening-Balances"]);
+        Ledger::builder()
+            .directives(vec![
+                Directive::Pad(
+                    Pad::builder()
+                        .date(Date::from_str_unchecked("2002-01-17"))
+                        .pad_to_account(checking.clone())
+                        .pad_from_account(opening)
+                        .build(),
+                ),
+                balance("2014-07-09", checking, Decimal::new(98700, 2), "USD"),
+            ])
+            .build()
+    }
+
+    #[test]
+    fn default_options() {
+        let padded = apply_pads(&ledger(), &PadOptions::default());
+        assert_eq!(padded.directives.len(), 3);
+        let txn = match &padded.directives[1] {
+            Directive::Transaction(txn) => txn,
+            other => panic!("expected a transaction, got {:?}", other),
+        };
+        assert_eq!(txn.flag, Flag::Padding);
+        assert_eq!(
+            txn.narration,
+            "(Padding inserted for Balance of 987.00 USD for difference 987.00 USD)"
+        );
+        assert_eq!(txn.date, Date::from_str_unchecked("2002-01-17"));
+        assert_eq!(verify_balances(&padded), vec![]);
+    }
+
+    #[test]
+    fn custom_options() {
+        let options = PadOptions::builder()
+            .flag(Flag::Warning)
+            .narration(Some("Opening balance".into()))
+            .build();
+        let padded = apply_pads(&ledger(), &options);
+        let txn = match &padded.directives[1] {
+            Directive::Transaction(txn) => txn,
+            other => panic!("expected a transaction, got {:?}", other),
+        };
+        assert_eq!(txn.flag, Flag::Warning);
+        assert_eq!(txn.narration, "Opening balance");
+        assert_eq!(verify_balances(&padded), vec![]);
+    }
+
+    #[test]
+    fn elided_posting_to_padded_account() {
+        let mut ledger = ledger();
+        let salary = account(AccountType::Income, &["Salary"]);
+        let checking = account(AccountType::Assets, &["BofA", "Checking"]);
+        ledger.directives.insert(
+            1,
+            Directive::Transaction(transaction(
+                "2005-01-01",
+                vec![posting(salary, -100, "USD"), elided(checking)],
+            )),
+        );
+
+        let padded = apply_pads(&ledger, &PadOptions::default());
+        let txn = match &padded.directives[1] {
+            Directive::Transaction(txn) => txn,
+            other => panic!("expected a transaction, got {:?}", other),
+        };
+        assert_eq!(
+            txn.postings[0].units,
+            Amount::new(Decimal::new(88700, 2), "USD").into()
+        );
+        assert_eq!(verify_balances(&padded), vec![]);
+    }
+
+    #[test]
+    fn pad_without_balance_is_noop() {
+        let mut ledger = ledger();
+        ledger.directives.pop();
+        let padded = apply_pads(&ledger, &PadOptions::default());
+        assert_eq!(padded, ledger);
+    }
+
+    #[test]
+    fn latest_pad_is_used() {
+        let mut ledger = ledger();
+        let savings = account(AccountType::Assets, &["BofA", "Savings"]);
+        let mut later = match &ledger.directives[0] {
+            Directive::Pad(pad) => pad.clone(),
+            other => panic!("expected a pad, got {:?}", other),
+        };
+        later.date = Date::from_str_unchecked("2010-01-01");
+        later.pad_from_account = savings.clone();
+        ledger.directives.insert(1, Directive::Pad(later));
+
+        let padded = apply_pads(&ledger, &PadOptions::default());
+        assert_eq!(padded.directives.len(), 4);
+        let txn = match &padded.directives[2] {
+            Directive::Transaction(txn) => txn,
+            other => panic!("expected a transaction, got {:?}", other),
+        };
+        assert_eq!(txn.date, Date::from_str_unchecked("2010-01-01"));
+        assert_eq!(txn.postings[1].account, savings);
+        assert_eq!(verify_balances(&padded), vec![]);
+    }
+}
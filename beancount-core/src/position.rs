@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::hash::{Hash, Hasher};
 
 use rust_decimal::Decimal;
 use typed_builder::TypedBuilder;
@@ -7,6 +8,7 @@ use super::amount::Amount;
 use super::{Currency, Date};
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cost<'a> {
     pub number: Decimal,
     pub currency: Currency<'a>,
@@ -14,14 +16,15 @@ pub struct Cost<'a> {
     pub label: Option<Cow<'a, str>>,
 }
 
-// TODO: Important Note. Amounts specified as either per-share or total prices or costs are always
-// unsigned. It is an error to use a negative sign or a negative cost and Beancount will raise an
-// error if you attempt to do so.
-
 /// Represents a "cost", which typically belongs to a [Posting](struct.Posting.html).
 ///
+/// Per-unit and total costs are always unsigned; the parser rejects negative ones.
+///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.mtqrwt24wnzs>
-#[derive(Clone, Debug, Eq, PartialEq, Hash, TypedBuilder)]
+///
+/// Cost specs compare and hash without their raw literals, which only affect rendering.
+#[derive(Clone, Debug, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CostSpec<'a> {
     #[builder(default)]
     pub number_per: Option<Decimal>,
@@ -39,10 +42,122 @@ pub struct CostSpec<'a> {
     /// Flag to indicate that all lots should be merged and average cost to be used
     #[builder(default)]
     pub merge_cost: bool,
+    /// `number_per` as written in the input, if the parser was asked to keep it.
+    #[builder(default)]
+    pub raw_number_per: Option<Cow<'a, str>>,
+    /// `number_total` as written in the input, if the parser was asked to keep it.
+    #[builder(default)]
+    pub raw_number_total: Option<Cow<'a, str>>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position<'a> {
     pub units: Amount<'a>,
     pub cost: Option<Cost<'a>>,
 }
+
+impl Cost<'_> {
+    /// Copies the cost so that it no longer borrows the parsed input.
+    pub fn into_owned(self) -> Cost<'static> {
+        Cost {
+            number: self.number,
+            currency: crate::owned(self.currency),
+            date: self.date.into_owned(),
+            label: self.label.map(crate::owned),
+        }
+    }
+}
+
+impl CostSpec<'_> {
+    /// Whether the cost has no components at all, as parsed from `{}`.
+    pub fn is_empty(&self) -> bool {
+        self.number_per.is_none()
+            && self.number_total.is_none()
+            && self.currency.is_none()
+            && self.date.is_none()
+            && self.label.is_none()
+            && !self.merge_cost
+    }
+
+    /// Copies the cost so that it no longer borrows the parsed input.
+    pub fn into_owned(self) -> CostSpec<'static> {
+        CostSpec {
+            number_per: self.number_per,
+            number_total: self.number_total,
+            currency: self.currency.map(crate::owned),
+            date: self.date.map(Date::into_owned),
+            label: self.label.map(crate::owned),
+            merge_cost: self.merge_cost,
+            raw_number_per: self.raw_number_per.map(crate::owned),
+            raw_number_total: self.raw_number_total.map(crate::owned),
+        }
+    }
+}
+
+impl PartialEq for CostSpec<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.number_per == other.number_per
+            && self.number_total == other.number_total
+            && self.currency == other.currency
+            && self.date == other.date
+            && self.label == other.label
+            && self.merge_cost == other.merge_cost
+    }
+}
+
+impl Eq for CostSpec<'_> {}
+
+impl Hash for CostSpec<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.number_per.hash(state);
+        self.number_total.hash(state);
+        self.currency.hash(state);
+        self.date.hash(state);
+        self.label.hash(state);
+        self.merge_cost.hash(state);
+    }
+}
+
+impl Position<'_> {
+    /// Copies the position so that it no longer borrows the parsed input.
+    pub fn into_owned(self) -> Position<'static> {
+        Position {
+            units: self.units.into_owned(),
+            cost: self.cost.map(Cost::into_owned),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::MetaValue;
+
+    #[test]
+    fn empty_cost_spec() {
+        assert!(CostSpec::builder().build().is_empty());
+        assert!(!CostSpec::builder().merge_cost(true).build().is_empty());
+        assert!(!CostSpec::builder()
+            .label(Some("lot".into()))
+            .build()
+            .is_empty());
+    }
+
+    #[test]
+    fn cost_and_amount_numbers_mix() {
+        let units = Amount::new(Decimal::new(10, 0), "HOOL");
+        let cost = CostSpec::builder()
+            .number_per(Some(Decimal::new(1525, 2)))
+            .currency(Some("USD".into()))
+            .build();
+
+        let number_per = cost.number_per.unwrap();
+        assert!(number_per > units.num);
+        assert_eq!(number_per * units.num, Decimal::new(15250, 2));
+
+        let price = Amount::new(Decimal::new(1525, 2), "USD");
+        assert_eq!(number_per, price.num);
+        assert_eq!(MetaValue::Number(number_per), MetaValue::Number(price.num));
+    }
+}
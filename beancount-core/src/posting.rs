@@ -1,9 +1,12 @@
+use std::convert::TryFrom;
+
+use rust_decimal::Decimal;
 use typed_builder::TypedBuilder;
 
 use super::account::Account;
-use super::amount::IncompleteAmount;
+use super::amount::{Amount, IncompleteAmount};
 use super::flags::Flag;
-use super::metadata::Meta;
+use super::metadata::{owned_meta, Meta};
 use super::position::CostSpec;
 
 /// Represents a transaction posting.  Postings represent a single amount being deposited to or
@@ -18,7 +21,8 @@ use super::position::CostSpec;
 ///     Assets:FR:SocGen:Checking          436.01 CAD
 /// ```
 ///
-/// A posting with a cost is the same with the exception that it utilizes `@@`.
+/// The price can also be given for all units at once, with `@@`; see
+/// [`PriceSpec`](enum.PriceSpec.html).
 ///
 /// ```text
 /// 2012-11-03 * "Transfer to account in Canada"
@@ -28,6 +32,7 @@ use super::position::CostSpec;
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.mtqrwt24wnzs>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Posting<'a> {
     /// Account being posted to.
     pub account: Account<'a>,
@@ -50,8 +55,127 @@ pub struct Posting<'a> {
     pub meta: Meta<'a>,
 }
 
+impl<'a> Posting<'a> {
+    /// The amount this posting contributes to the balance of its transaction: the units at cost
+    /// if the posting has a cost, the units at price if it has a price, and the units otherwise.
+    /// Total costs and prices (`{{...}}` and `@@`) take the sign of the units.
+    ///
+    /// Returns `None` if the units, or the cost or price used, are incomplete.
+    pub fn weight(&self) -> Option<Amount<'a>> {
+        let units = self.units.num?;
+        let signed = |total: Decimal| {
+            if units.is_sign_negative() {
+                -total
+            } else {
+                total
+            }
+        };
+        if let Some(cost) = &self.cost {
+            if cost.number_per.is_none() && cost.number_total.is_none() {
+                return None;
+            }
+            let num = cost.number_per.map_or(Decimal::ZERO, |per| units * per)
+                + cost.number_total.map_or(Decimal::ZERO, signed);
+            return Some(Amount::new(num, cost.currency.clone()?));
+        }
+        if let Some(price) = &self.price {
+            let amount = price.amount();
+            let num = match price {
+                PriceSpec::PerUnit(_) => units * amount.num?,
+                PriceSpec::Total(_) => signed(amount.num?),
+            };
+            return Some(Amount::new(num, amount.currency.clone()?));
+        }
+        Amount::try_from(self.units.clone()).ok()
+    }
+
+    /// Copies the posting so that it no longer borrows the parsed input.
+    pub fn into_owned(self) -> Posting<'static> {
+        Posting {
+            account: self.account.into_owned(),
+            units: self.units.into_owned(),
+            cost: self.cost.map(CostSpec::into_owned),
+            price: self.price.map(PriceSpec::into_owned),
+            flag: self.flag.map(Flag::into_owned),
+            meta: owned_meta(self.meta),
+        }
+    }
+}
+
+/// The price of a posting, as written with `@` or `@@`.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PriceSpec<'a> {
+    /// The price of one unit, written `@ 1.09 CAD`.
     PerUnit(IncompleteAmount<'a>),
+    /// The price of all the units of the posting, written `@@ 436.01 CAD`.
     Total(IncompleteAmount<'a>),
 }
+
+impl<'a> PriceSpec<'a> {
+    /// Copies the price so that it no longer borrows the parsed input.
+    pub fn into_owned(self) -> PriceSpec<'static> {
+        match self {
+            PriceSpec::PerUnit(amount) => PriceSpec::PerUnit(amount.into_owned()),
+            PriceSpec::Total(amount) => PriceSpec::Total(amount.into_owned()),
+        }
+    }
+
+    /// The price amount, regardless of whether it is per-unit or total.
+    pub fn amount(&self) -> &IncompleteAmount<'a> {
+        match self {
+            PriceSpec::PerUnit(amount) | PriceSpec::Total(amount) => amount,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{amount, assets, posting};
+
+    #[test]
+    fn plain_weight() {
+        assert_eq!(
+            posting(assets("Brokerage"), -400, "USD").weight(),
+            Some(Amount::new(Decimal::new(-400, 0), "USD"))
+        );
+
+        let mut elided = posting(assets("Brokerage"), -400, "USD");
+        elided.units.num = None;
+        assert_eq!(elided.weight(), None);
+    }
+
+    #[test]
+    fn per_unit_cost_weight() {
+        let mut posting = posting(assets("Brokerage"), 10, "HOOL");
+        posting.cost = Some(
+            CostSpec::builder()
+                .number_per(Some(Decimal::new(51825, 2)))
+                .currency(Some("USD".into()))
+                .build(),
+        );
+        // A price is ignored when there is a cost.
+        posting.price = Some(PriceSpec::PerUnit(amount(520, "USD")));
+        assert_eq!(
+            posting.weight(),
+            Some(Amount::new(Decimal::new(518250, 2), "USD"))
+        );
+    }
+
+    #[test]
+    fn total_price_weight() {
+        let mut posting = posting(assets("Brokerage"), -400, "USD");
+        posting.price = Some(PriceSpec::Total(amount(436, "CAD")));
+        assert_eq!(
+            posting.weight(),
+            Some(Amount::new(Decimal::new(-436, 0), "CAD"))
+        );
+
+        posting.price = Some(PriceSpec::PerUnit(amount(2, "CAD")));
+        assert_eq!(
+            posting.weight(),
+            Some(Amount::new(Decimal::new(-800, 0), "CAD"))
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rust_decimal::Decimal;
+
+use super::account::Account;
+use super::directives::{Directive, Transaction};
+use super::inventory::Inventory;
+use super::{Currency, Date, Ledger};
+
+/// Balances smaller than this are treated as zero when looking for held commodities.
+const HELD_TOLERANCE: Decimal = Decimal::from_parts(1, 0, 0, false, 9);
+
+/// A reporting period that transactions can be grouped by.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Period {
+    /// Calendar months, keyed as `YYYY-MM`.
+    Month,
+    /// Calendar years, keyed as `YYYY`.
+    Year,
+}
+
+impl<'a> Ledger<'a> {
+    /// Sums the units of every posting in the ledger per commodity.
+    ///
+    /// Since every transaction balances, the totals are zero unless a transaction is unbalanced
+    /// or converts between commodities without a matching conversion entry. Elided amounts are
+    /// inferred with [`Transaction::solved_units`](struct.Transaction.html#method.solved_units);
+    /// postings whose units can't be determined are left out.
+    pub fn trial_balance(&self) -> HashMap<Currency<'a>, Decimal> {
+        let mut totals: HashMap<Currency<'a>, Decimal> = HashMap::new();
+        for directive in &self.directives {
+            if let Directive::Transaction(txn) = directive {
+                for (_, units) in txn.solved_units() {
+                    *totals.entry(units.currency).or_default() += units.num;
+                }
+            }
+        }
+        totals
+    }
+
+    /// Whether the [trial balance](#method.trial_balance) is zero in every commodity.
+    pub fn is_balanced(&self) -> bool {
+        self.trial_balance().values().all(Decimal::is_zero)
+    }
+
+    /// Sums, per payee and commodity, the postings to accounts matching `account_filter` in
+    /// transactions dated from `from` up to and including `to`.
+    ///
+    /// Transactions without a payee are grouped under the empty string. Only postings with both
+    /// a number and a commodity are summed.
+    pub fn spending_by_payee<F>(
+        &self,
+        account_filter: F,
+        from: &Date<'a>,
+        to: &Date<'a>,
+    ) -> HashMap<String, HashMap<Currency<'a>, Decimal>>
+    where
+        F: Fn(&Account<'a>) -> bool,
+    {
+        let mut totals: HashMap<String, HashMap<Currency<'a>, Decimal>> = HashMap::new();
+        for directive in &self.directives {
+            let txn = match directive {
+                Directive::Transaction(txn) if txn.date >= *from && txn.date <= *to => txn,
+                _ => continue,
+            };
+            for posting in txn.postings.iter().filter(|p| account_filter(&p.account)) {
+                if let (Some(num), Some(currency)) = (posting.units.num, &posting.units.currency) {
+                    let payee = txn.payee.as_deref().unwrap_or_default();
+                    *totals
+                        .entry(payee.to_string())
+                        .or_default()
+                        .entry(currency.clone())
+                        .or_default() += num;
+                }
+            }
+        }
+        totals
+    }
+
+    /// Groups the ledger's transactions by the period they fall in, keyed as `YYYY-MM` for
+    /// [`Period::Month`](enum.Period.html#variant.Month) and `YYYY` for
+    /// [`Period::Year`](enum.Period.html#variant.Year). Transactions keep their order in the
+    /// ledger within a period; those whose date isn't a valid calendar date are left out.
+    pub fn group_by_period(&self, period: Period) -> BTreeMap<String, Vec<&Transaction<'a>>> {
+        let mut groups: BTreeMap<String, Vec<&Transaction<'a>>> = BTreeMap::new();
+        for directive in &self.directives {
+            if let Directive::Transaction(txn) = directive {
+                let (year, month) = match (txn.date.year(), txn.date.month()) {
+                    (Some(year), Some(month)) => (year, month),
+                    _ => continue,
+                };
+                let key = match period {
+                    Period::Month => format!("{:04}-{:02}", year, month),
+                    Period::Year => format!("{:04}", year),
+                };
+                groups.entry(key).or_default().push(txn);
+            }
+        }
+        groups
+    }
+
+    /// Pairs each opened account with its open date and close date, the latter being `None` for
+    /// accounts that are still open.
+    ///
+    /// If an account is opened more than once, the first open in the ledger is kept. Closes of
+    /// accounts that were never opened are ignored.
+    pub fn account_lifecycles(&self) -> HashMap<Account<'a>, (Date<'a>, Option<Date<'a>>)> {
+        let mut lifecycles: HashMap<Account<'a>, (Date<'a>, Option<Date<'a>>)> = HashMap::new();
+        for directive in &self.directives {
+            if let Directive::Open(open) = directive {
+                lifecycles
+                    .entry(open.account.clone())
+                    .or_insert_with(|| (open.date.clone(), None));
+            }
+        }
+        for directive in &self.directives {
+            if let Directive::Close(close) = directive {
+                if let Some((_, closed)) = lifecycles.get_mut(&close.account) {
+                    *closed = Some(close.date.clone());
+                }
+            }
+        }
+        lifecycles
+    }
+
+    /// Commodities with a nonzero balance in `account` at the end of `date`, sorted by name.
+    ///
+    /// The units of the account's postings are accumulated in an
+    /// [`Inventory`](inventory/struct.Inventory.html), with elided amounts inferred by
+    /// [`Transaction::solved_units`](struct.Transaction.html#method.solved_units). Balances
+    /// within a billionth of a unit of zero are treated as zero.
+    pub fn held_commodities(&self, account: &Account<'a>, date: &Date<'a>) -> Vec<Currency<'a>> {
+        let mut inventory = Inventory::new();
+        for directive in &self.directives {
+            if let Directive::Transaction(txn) = directive {
+                if txn.date > *date {
+                    continue;
+                }
+                for (posting, units) in txn.solved_units() {
+                    if posting.account == *account {
+                        inventory.add_amount(units);
+                    }
+                }
+            }
+        }
+        inventory
+            .units()
+            .into_iter()
+            .filter(|amount| amount.num.abs() >= HELD_TOLERANCE)
+            .map(|amount| amount.currency)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{amount, assets, elided, open, posting, transaction};
+    use crate::*;
+
+    /// A transaction on `date` with the given postings to `Assets` accounts.
+    fn dated(
+        date: &'static str,
+        postings: &[(&'static str, i64, &'static str)],
+    ) -> Directive<'static> {
+        Directive::Transaction(transaction(
+            date,
+            postings
+                .iter()
+                .map(|&(name, num, currency)| posting(assets(name), num, currency))
+                .collect(),
+        ))
+    }
+
+    #[test]
+    fn balanced_ledger() {
+        let ledger = Ledger::builder()
+            .directives(vec![
+                dated("2020-01-01", &[("Cash", -10, "USD"), ("Bank", 10, "USD")]),
+                dated("2020-01-01", &[("Cash", 5, "EUR"), ("Bank", -5, "EUR")]),
+            ])
+            .build();
+        let trial_balance = ledger.trial_balance();
+        assert_eq!(trial_balance.len(), 2);
+        assert_eq!(trial_balance["USD"], Decimal::ZERO);
+        assert_eq!(trial_balance["EUR"], Decimal::ZERO);
+        assert!(ledger.is_balanced());
+    }
+
+    #[test]
+    fn unbalanced_ledger() {
+        let ledger = Ledger::builder()
+            .directives(vec![dated(
+                "2020-01-01",
+                &[("Cash", -10, "USD"), ("Bank", 9, "USD")],
+            )])
+            .build();
+        assert_eq!(ledger.trial_balance()["USD"], Decimal::from(-1));
+        assert!(!ledger.is_balanced());
+    }
+
+    #[test]
+    fn elided_postings_are_inferred() {
+        let mut converted = posting(assets("Cash"), -10, "USD");
+        converted.price = Some(PriceSpec::PerUnit(amount(Decimal::new(9, 1), "EUR")));
+        let ledger = Ledger::builder()
+            .directives(vec![
+                Directive::Transaction(transaction(
+                    "2020-01-01",
+                    vec![posting(assets("Cash"), -10, "USD"), elided(assets("Bank"))],
+                )),
+                Directive::Transaction(transaction(
+                    "2020-01-01",
+                    vec![converted, elided(assets("Bank"))],
+                )),
+            ])
+            .build();
+        let trial_balance = ledger.trial_balance();
+        assert_eq!(trial_balance["USD"], Decimal::from(-10));
+        assert_eq!(trial_balance["EUR"], Decimal::from(9));
+        assert!(!ledger.is_balanced());
+    }
+
+    #[test]
+    fn held_commodities() {
+        let ledger = Ledger::builder()
+            .directives(vec![
+                dated("2020-01-01", &[("Cash", 10, "USD"), ("Bank", -10, "USD")]),
+                dated("2020-01-02", &[("Cash", 5, "EUR"), ("Bank", -5, "EUR")]),
+                dated("2020-01-03", &[("Cash", -5, "EUR"), ("Bank", 5, "EUR")]),
+                dated("2020-01-04", &[("Cash", 1, "CAD"), ("Bank", -1, "CAD")]),
+                Directive::Transaction(transaction(
+                    "2020-01-05",
+                    vec![posting(assets("Bank"), 2, "GBP"), elided(assets("Cash"))],
+                )),
+            ])
+            .build();
+        let cash = assets("Cash");
+        assert_eq!(
+            ledger.held_commodities(&cash, &Date::from_str_unchecked("2020-01-02")),
+            vec!["EUR", "USD"]
+        );
+        assert_eq!(
+            ledger.held_commodities(&cash, &Date::from_str_unchecked("2020-01-03")),
+            vec!["USD"]
+        );
+        assert_eq!(
+            ledger.held_commodities(&cash, &Date::from_str_unchecked("2020-01-04")),
+            vec!["CAD", "USD"]
+        );
+        assert_eq!(
+            ledger.held_commodities(&cash, &Date::from_str_unchecked("2020-12-31")),
+            vec!["CAD", "GBP", "USD"]
+        );
+    }
+
+    #[test]
+    fn spending_by_payee() {
+        let purchase = |date: &'static str, payee: Option<&'static str>, num: i64| {
+            let mut directive = dated(date, &[("Food", num, "USD"), ("Cash", -num, "USD")]);
+            if let Directive::Transaction(txn) = &mut directive {
+                txn.payee = payee.map(Into::into);
+            }
+            directive
+        };
+        let ledger = Ledger::builder()
+            .directives(vec![
+                purchase("2020-01-01", Some("Grocer"), 10),
+                purchase("2020-01-15", Some("Grocer"), 5),
+                purchase("2020-01-20", None, 3),
+                purchase("2020-02-01", Some("Grocer"), 7),
+            ])
+            .build();
+        let spending = ledger.spending_by_payee(
+            |account| account.parts == ["Food"],
+            &Date::from_str_unchecked("2020-01-01"),
+            &Date::from_str_unchecked("2020-01-31"),
+        );
+        assert_eq!(spending.len(), 2);
+        assert_eq!(spending["Grocer"]["USD"], Decimal::from(15));
+        assert_eq!(spending[""]["USD"], Decimal::from(3));
+    }
+
+    #[test]
+    fn account_lifecycles() {
+        let ledger = Ledger::builder()
+            .directives(vec![
+                open("2020-01-01", assets("Cash")),
+                open("2020-02-01", assets("Bank")),
+                Directive::Close(
+                    Close::builder()
+                        .date(Date::from_str_unchecked("2020-06-30"))
+                        .account(assets("Cash"))
+                        .build(),
+                ),
+            ])
+            .build();
+
+        let lifecycles = ledger.account_lifecycles();
+        assert_eq!(lifecycles.len(), 2);
+        assert_eq!(
+            lifecycles[&assets("Cash")],
+            (
+                Date::from_str_unchecked("2020-01-01"),
+                Some(Date::from_str_unchecked("2020-06-30"))
+            )
+        );
+        assert_eq!(
+            lifecycles[&assets("Bank")],
+            (Date::from_str_unchecked("2020-02-01"), None)
+        );
+    }
+
+    #[test]
+    fn group_by_period() {
+        let ledger = Ledger::builder()
+            .directives(vec![
+                dated("2020-01-31", &[]),
+                dated("2020/02/01", &[]),
+                dated("2020-01-01", &[]),
+                dated("2021-02-15", &[]),
+                dated("2020-1-05", &[]),
+                dated("2020-13-01", &[]),
+            ])
+            .build();
+        let dates = |txns: &Vec<&Transaction<'_>>| -> Vec<String> {
+            txns.iter().map(|txn| txn.date.to_string()).collect()
+        };
+
+        let months = ledger.group_by_period(Period::Month);
+        assert_eq!(
+            months.keys().collect::<Vec<_>>(),
+            vec!["2020-01", "2020-02", "2021-02"]
+        );
+        assert_eq!(
+            dates(&months["2020-01"]),
+            vec!["2020-01-31", "2020-01-01", "2020-1-05"]
+        );
+        assert_eq!(dates(&months["2020-02"]), vec!["2020/02/01"]);
+
+        let years = ledger.group_by_period(Period::Year);
+        assert_eq!(years.keys().collect::<Vec<_>>(), vec!["2020", "2021"]);
+        assert_eq!(years["2020"].len(), 4);
+    }
+}
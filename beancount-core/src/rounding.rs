@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use super::amount::{Amount, IncompleteAmount};
+use super::directives::Directive;
+use super::posting::PriceSpec;
+use super::{Currency, Ledger};
+
+/// Rounds every amount, cost and price in the ledger to the scale configured for its commodity
+/// in `scales`, using `strategy`. Numbers are also padded with zeros up to the scale, so `5 USD`
+/// becomes `5.00 USD` for a scale of 2.
+///
+/// Numbers in commodities without a configured scale, and numbers with no commodity, are left
+/// untouched. The raw literals of rounded numbers are dropped.
+pub fn round_to_currency_scale(
+    ledger: &mut Ledger<'_>,
+    scales: &HashMap<Currency<'_>, u32>,
+    strategy: RoundingStrategy,
+) {
+    // Whether the number was rounded.
+    let round = |num: &mut Decimal, currency: &str| match scales.get(currency) {
+        Some(&scale) => {
+            *num = num.round_dp_with_strategy(scale, strategy);
+            num.rescale(scale);
+            true
+        }
+        None => false,
+    };
+    let round_amount = |amount: &mut Amount<'_>| {
+        if round(&mut amount.num, &amount.currency) {
+            amount.raw = None;
+        }
+    };
+    let round_incomplete = |amount: &mut IncompleteAmount<'_>| {
+        if let (Some(num), Some(currency)) = (&mut amount.num, &amount.currency) {
+            if round(num, currency) {
+                amount.raw = None;
+            }
+        }
+    };
+
+    for directive in &mut ledger.directives {
+        match directive {
+            Directive::Balance(balance) => round_amount(&mut balance.amount),
+            Directive::Price(price) => round_amount(&mut price.amount),
+            Directive::Transaction(txn) => {
+                for posting in &mut txn.postings {
+                    round_incomplete(&mut posting.units);
+                    if let Some(cost) = &mut posting.cost {
+                        if let Some(currency) = &cost.currency {
+                            for (num, raw) in [
+                                (&mut cost.number_per, &mut cost.raw_number_per),
+                                (&mut cost.number_total, &mut cost.raw_number_total),
+                            ] {
+                                if let Some(num) = num {
+                                    if round(num, currency) {
+                                        *raw = None;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    match &mut posting.price {
+                        Some(PriceSpec::PerUnit(amount)) | Some(PriceSpec::Total(amount)) => {
+                            round_incomplete(amount)
+                        }
+                        None => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Ledger<'_> {
+    /// Rounds every amount, cost and price in the ledger to the display precision of its
+    /// commodity, e.g. 2 for `USD` and 8 for `BTC`, like beancount's `display_precision`.
+    /// Midpoints are rounded to the nearest even digit.
+    ///
+    /// See [`round_to_currency_scale`](rounding/fn.round_to_currency_scale.html) for details and
+    /// other rounding strategies.
+    pub fn round_to_precision(&mut self, precisions: &HashMap<Currency<'_>, u32>) {
+        round_to_currency_scale(self, precisions, RoundingStrategy::MidpointNearestEven);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{assets, posting, transaction};
+    use crate::*;
+
+    #[test]
+    fn round_to_currency_scale() {
+        let mut ledger = Ledger::builder()
+            .directives(vec![Directive::Transaction(transaction(
+                "2020-01-01",
+                vec![
+                    posting(assets("Cash"), Decimal::new(10005, 3), "USD"),
+                    posting(assets("Cash"), Decimal::new(5, 0), "USD"),
+                    posting(assets("Cash"), Decimal::new(123456, 5), "BTC"),
+                ],
+            ))])
+            .build();
+        let scales = vec![("USD".into(), 2)].into_iter().collect();
+        super::round_to_currency_scale(&mut ledger, &scales, RoundingStrategy::MidpointNearestEven);
+
+        let nums: Vec<_> = ledger
+            .postings()
+            .map(|(_, posting)| posting.units.num.unwrap().to_string())
+            .collect();
+        assert_eq!(nums, vec!["10.00", "5.00", "1.23456"]);
+    }
+
+    #[test]
+    fn round_to_precision() {
+        let mut ledger = Ledger::builder()
+            .directives(vec![Directive::Price(
+                Price::builder()
+                    .date(Date::from_str_unchecked("2020-01-01"))
+                    .currency("BTC".into())
+                    .amount(Amount::new(Decimal::new(9_123_455, 3), "USD"))
+                    .build(),
+            )])
+            .build();
+        let precisions = vec![("USD".into(), 2), ("BTC".into(), 8)]
+            .into_iter()
+            .collect();
+        ledger.round_to_precision(&precisions);
+        assert_eq!(
+            ledger.prices().next().unwrap().amount,
+            Amount::new(Decimal::new(912_346, 2), "USD")
+        );
+    }
+}
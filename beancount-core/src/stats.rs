@@ -0,0 +1,212 @@
+use std::collections::HashSet;
+
+use super::account::Account;
+use super::directives::Directive;
+use super::{Date, Ledger};
+
+/// Number of directives of each type in a ledger.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DirectiveCounts {
+    pub open: usize,
+    pub close: usize,
+    pub balance: usize,
+    pub option: usize,
+    pub commodity: usize,
+    pub custom: usize,
+    pub document: usize,
+    pub event: usize,
+    pub include: usize,
+    pub note: usize,
+    pub pad: usize,
+    pub plugin: usize,
+    pub price: usize,
+    pub query: usize,
+    pub transaction: usize,
+    pub unsupported: usize,
+}
+
+/// Summary statistics of a ledger, as computed by [`Ledger::stats`](../struct.Ledger.html#method.stats).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LedgerStats<'a> {
+    /// Number of directives of each type.
+    pub directives: DirectiveCounts,
+
+    /// Earliest date of any dated directive.
+    pub first_date: Option<Date<'a>>,
+
+    /// Latest date of any dated directive.
+    pub last_date: Option<Date<'a>>,
+
+    /// Number of distinct accounts referenced anywhere in the ledger.
+    pub accounts: usize,
+
+    /// Number of distinct commodities referenced anywhere in the ledger.
+    pub commodities: usize,
+}
+
+impl<'a> Ledger<'a> {
+    /// Computes summary statistics of the ledger in a single pass over its directives.
+    pub fn stats(&self) -> LedgerStats<'a> {
+        let mut counts = DirectiveCounts::default();
+        let mut first_date: Option<&Date<'a>> = None;
+        let mut last_date: Option<&Date<'a>> = None;
+        let mut accounts: HashSet<&Account<'a>> = HashSet::new();
+        let mut commodities: HashSet<&str> = HashSet::new();
+
+        for directive in &self.directives {
+            if let Some(date) = directive.date() {
+                if first_date.is_none_or(|first| date < first) {
+                    first_date = Some(date);
+                }
+                if last_date.is_none_or(|last| date > last) {
+                    last_date = Some(date);
+                }
+            }
+
+            match directive {
+                Directive::Open(open) => {
+                    counts.open += 1;
+                    accounts.insert(&open.account);
+                    commodities.extend(open.currencies.iter().map(|c| c.as_ref()));
+                }
+                Directive::Close(close) => {
+                    counts.close += 1;
+                    accounts.insert(&close.account);
+                }
+                Directive::Balance(balance) => {
+                    counts.balance += 1;
+                    accounts.insert(&balance.account);
+                    commodities.insert(&balance.amount.currency);
+                }
+                Directive::Option(_) => counts.option += 1,
+                Directive::Commodity(commodity) => {
+                    counts.commodity += 1;
+                    commodities.insert(&commodity.name);
+                }
+                Directive::Custom(_) => counts.custom += 1,
+                Directive::Document(document) => {
+                    counts.document += 1;
+                    accounts.insert(&document.account);
+                }
+                Directive::Event(_) => counts.event += 1,
+                Directive::Include(_) => counts.include += 1,
+                Directive::Note(note) => {
+                    counts.note += 1;
+                    accounts.insert(&note.account);
+                }
+                Directive::Pad(pad) => {
+                    counts.pad += 1;
+                    accounts.insert(&pad.pad_to_account);
+                    accounts.insert(&pad.pad_from_account);
+                }
+                Directive::Plugin(_) => counts.plugin += 1,
+                Directive::Price(price) => {
+                    counts.price += 1;
+                    commodities.insert(&price.currency);
+                    commodities.insert(&price.amount.currency);
+                }
+                Directive::Query(_) => counts.query += 1,
+                Directive::Transaction(txn) => {
+                    counts.transaction += 1;
+                    for posting in &txn.postings {
+                        accounts.insert(&posting.account);
+                        commodities.extend(posting.units.currency.as_deref());
+                        if let Some(cost) = &posting.cost {
+                            commodities.extend(cost.currency.as_deref());
+                        }
+                        if let Some(price) = &posting.price {
+                            commodities.extend(price.amount().currency.as_deref());
+                        }
+                    }
+                }
+                Directive::Unsupported(_) => counts.unsupported += 1,
+            }
+        }
+
+        LedgerStats {
+            directives: counts,
+            first_date: first_date.cloned(),
+            last_date: last_date.cloned(),
+            accounts: accounts.len(),
+            commodities: commodities.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{account, open, posting, transaction};
+    use crate::*;
+
+    #[test]
+    fn stats() {
+        let cash = account(AccountType::Assets, &["Cash"]);
+        let food = account(AccountType::Expenses, &["Food"]);
+        let mut open_cash = open("2020-01-01", cash.clone());
+        if let Directive::Open(open) = &mut open_cash {
+            open.currencies = vec!["USD".into()];
+        }
+        let ledger = Ledger::builder()
+            .directives(vec![
+                Directive::Option(
+                    BcOption::builder()
+                        .name("title".into())
+                        .val("Stats".into())
+                        .build(),
+                ),
+                open_cash,
+                Directive::Transaction(transaction(
+                    "2020-03-01",
+                    vec![
+                        posting(cash.clone(), -10, "USD"),
+                        posting(food.clone(), 10, "USD"),
+                    ],
+                )),
+                Directive::Transaction(transaction(
+                    "2020-02-01",
+                    vec![posting(cash, -20, "EUR"), posting(food, 20, "EUR")],
+                )),
+                Directive::Price(
+                    Price::builder()
+                        .date(Date::from_str_unchecked("2020-02-15"))
+                        .currency("HOOL".into())
+                        .amount(
+                            Amount::builder()
+                                .num(500.into())
+                                .currency("USD".into())
+                                .build(),
+                        )
+                        .build(),
+                ),
+            ])
+            .build();
+
+        let stats = ledger.stats();
+        assert_eq!(
+            stats.directives,
+            DirectiveCounts {
+                option: 1,
+                open: 1,
+                transaction: 2,
+                price: 1,
+                ..DirectiveCounts::default()
+            }
+        );
+        assert_eq!(
+            stats.first_date,
+            Some(Date::from_str_unchecked("2020-01-01"))
+        );
+        assert_eq!(
+            stats.last_date,
+            Some(Date::from_str_unchecked("2020-03-01"))
+        );
+        assert_eq!(stats.accounts, 2);
+        assert_eq!(stats.commodities, 3);
+    }
+
+    #[test]
+    fn stats_of_empty_ledger() {
+        assert_eq!(Ledger::default().stats(), LedgerStats::default());
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use super::account::Account;
+use super::directives::{Directive, Open, Transaction};
+use super::{Currency, Date, Ledger};
+
+/// Any error found while validating a ledger.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ValidationError<'a> {
+    PostingCurrency(PostingCurrencyError<'a>),
+    DuplicateOpen(DuplicateOpenError<'a>),
+}
+
+impl<'a> From<PostingCurrencyError<'a>> for ValidationError<'a> {
+    fn from(err: PostingCurrencyError<'a>) -> Self {
+        ValidationError::PostingCurrency(err)
+    }
+}
+
+impl<'a> From<DuplicateOpenError<'a>> for ValidationError<'a> {
+    fn from(err: DuplicateOpenError<'a>) -> Self {
+        ValidationError::DuplicateOpen(err)
+    }
+}
+
+impl<'a> Ledger<'a> {
+    /// Lazily validates the ledger, yielding errors as they are found.
+    ///
+    /// Directives are only checked as the iterator is advanced, so a caller can stop after the
+    /// first few errors without validating the rest of the ledger.
+    pub fn validate_iter(&self) -> impl Iterator<Item = ValidationError<'a>> + '_ {
+        validate_directives(&self.directives)
+    }
+}
+
+/// Lazily validates a sequence of directives, checking each directive only once the previous
+/// one's errors have been consumed.
+pub fn validate_directives<'a, 'b, I>(
+    directives: I,
+) -> impl Iterator<Item = ValidationError<'a>> + 'b
+where
+    'a: 'b,
+    I: IntoIterator<Item = &'b Directive<'a>>,
+    I::IntoIter: 'b,
+{
+    let mut opens = HashMap::new();
+    directives.into_iter().flat_map(
+        move |directive| -> Box<dyn Iterator<Item = ValidationError<'a>> + 'b> {
+            match directive {
+                Directive::Transaction(txn) => {
+                    Box::new(posting_currency_errors(txn).map(ValidationError::from))
+                }
+                Directive::Open(open) => Box::new(
+                    duplicate_open_error(&mut opens, open)
+                        .into_iter()
+                        .map(ValidationError::from),
+                ),
+                _ => Box::new(std::iter::empty()),
+            }
+        },
+    )
+}
+
+/// A posting whose cost and price are stated in different commodities, e.g.
+/// `1 HOOL {500 USD} @ 10 EUR`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PostingCurrencyError<'a> {
+    /// Date of the transaction containing the posting.
+    pub date: Date<'a>,
+
+    /// Account of the offending posting.
+    pub account: Account<'a>,
+
+    /// Commodity of the posting's cost.
+    pub cost_currency: Currency<'a>,
+
+    /// Commodity of the posting's price.
+    pub price_currency: Currency<'a>,
+}
+
+/// Finds postings whose cost and price commodities disagree.
+///
+/// A posting held at cost is balanced using its cost, so a price in a different commodity can't
+/// be reconciled with the rest of the transaction and is almost always a typo.
+pub fn check_posting_currencies<'a>(ledger: &Ledger<'a>) -> Vec<PostingCurrencyError<'a>> {
+    ledger
+        .directives
+        .iter()
+        .filter_map(|directive| match directive {
+            Directive::Transaction(txn) => Some(txn),
+            _ => None,
+        })
+        .flat_map(posting_currency_errors)
+        .collect()
+}
+
+fn posting_currency_errors<'a, 'b>(
+    txn: &'b Transaction<'a>,
+) -> impl Iterator<Item = PostingCurrencyError<'a>> + 'b {
+    txn.postings.iter().filter_map(move |posting| {
+        let cost_currency = posting.cost.as_ref()?.currency.as_ref()?;
+        let price_currency = posting.price.as_ref()?.amount().currency.as_ref()?;
+        if cost_currency == price_currency {
+            return None;
+        }
+        Some(PostingCurrencyError {
+            date: txn.date.clone(),
+            account: posting.account.clone(),
+            cost_currency: cost_currency.clone(),
+            price_currency: price_currency.clone(),
+        })
+    })
+}
+
+/// An account opened by more than one `open` directive.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DuplicateOpenError<'a> {
+    /// The account opened more than once.
+    pub account: Account<'a>,
+
+    /// Date of the first `open` directive for the account.
+    pub first_date: Date<'a>,
+
+    /// Date of the duplicate `open` directive.
+    pub duplicate_date: Date<'a>,
+}
+
+/// Finds accounts opened more than once, reporting every `open` after the first in input order.
+pub fn check_duplicate_opens<'a>(ledger: &Ledger<'a>) -> Vec<DuplicateOpenError<'a>> {
+    let mut opens = HashMap::new();
+    ledger
+        .directives
+        .iter()
+        .filter_map(|directive| match directive {
+            Directive::Open(open) => duplicate_open_error(&mut opens, open),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Records `open` in `opens`, returning an error if its account was already opened.
+fn duplicate_open_error<'a, 'b>(
+    opens: &mut HashMap<&'b Account<'a>, &'b Date<'a>>,
+    open: &'b Open<'a>,
+) -> Option<DuplicateOpenError<'a>> {
+    match opens.entry(&open.account) {
+        Entry::Vacant(entry) => {
+            entry.insert(&open.date);
+            None
+        }
+        Entry::Occupied(entry) => Some(DuplicateOpenError {
+            account: open.account.clone(),
+            first_date: (*entry.get()).clone(),
+            duplicate_date: open.date.clone(),
+        }),
+    }
+}
+
+/// A posting that the `open` and `close` directives of its account don't allow.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LifecycleError<'a> {
+    /// Date of the transaction containing the posting.
+    pub date: Date<'a>,
+
+    /// Account of the offending posting.
+    pub account: Account<'a>,
+
+    /// Why the posting isn't allowed.
+    pub reason: LifecycleViolation<'a>,
+}
+
+/// The reason a posting violates the lifecycle of its account.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LifecycleViolation<'a> {
+    /// The account is never opened.
+    Unopened,
+    /// The posting is dated before the account is opened on the given date.
+    BeforeOpen(Date<'a>),
+    /// The posting is dated after the account is closed on the given date.
+    AfterClose(Date<'a>),
+    /// The posting's commodity is not among the commodities the account is opened with.
+    Currency(Currency<'a>),
+}
+
+/// Finds postings to accounts that aren't open on the posting's date, and postings in a
+/// commodity not allowed by the currency constraint of the account's `open` directive.
+///
+/// Like beancount, postings dated on the `close` date are allowed. Only the first `open` and
+/// `close` of each account are used. Errors are reported in date order.
+pub fn check_account_lifecycle<'a>(ledger: &Ledger<'a>) -> Vec<LifecycleError<'a>> {
+    let mut opens: HashMap<&Account<'a>, &Open<'a>> = HashMap::new();
+    let mut closes: HashMap<&Account<'a>, &Date<'a>> = HashMap::new();
+    for directive in &ledger.directives {
+        match directive {
+            Directive::Open(open) => {
+                opens.entry(&open.account).or_insert(open);
+            }
+            Directive::Close(close) => {
+                closes.entry(&close.account).or_insert(&close.date);
+            }
+            _ => {}
+        }
+    }
+
+    let mut transactions: Vec<_> = ledger.transactions().collect();
+    transactions.sort_by_key(|txn| &txn.date);
+    let mut errors = Vec::new();
+    for txn in transactions {
+        for posting in &txn.postings {
+            let reason = match opens.get(&posting.account) {
+                None => Some(LifecycleViolation::Unopened),
+                Some(open) if txn.date < open.date => {
+                    Some(LifecycleViolation::BeforeOpen(open.date.clone()))
+                }
+                Some(open) => match (closes.get(&posting.account), &posting.units.currency) {
+                    (Some(&close), _) if txn.date > *close => {
+                        Some(LifecycleViolation::AfterClose(close.clone()))
+                    }
+                    (_, Some(currency))
+                        if !open.currencies.is_empty() && !open.currencies.contains(currency) =>
+                    {
+                        Some(LifecycleViolation::Currency(currency.clone()))
+                    }
+                    _ => None,
+                },
+            };
+            if let Some(reason) = reason {
+                errors.push(LifecycleError {
+                    date: txn.date.clone(),
+                    account: posting.account.clone(),
+                    reason,
+                });
+            }
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{amount, assets, open, posting, transaction};
+    use crate::*;
+    use std::cell::Cell;
+
+    fn sale(price_currency: &'static str) -> Directive<'static> {
+        let mut sale = posting(assets("Trading"), -1, "HOOL");
+        sale.cost = Some(
+            CostSpec::builder()
+                .number_per(Some(500.into()))
+                .currency(Some("USD".into()))
+                .build(),
+        );
+        sale.price = Some(PriceSpec::PerUnit(amount(10, price_currency)));
+        Directive::Transaction(transaction("2020-10-01", vec![sale]))
+    }
+
+    #[test]
+    fn mismatched_cost_and_price() {
+        let ledger = Ledger::builder()
+            .directives(vec![sale("USD"), sale("EUR")])
+            .build();
+        let errors = check_posting_currencies(&ledger);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].cost_currency, "USD");
+        assert_eq!(errors[0].price_currency, "EUR");
+        assert_eq!(errors[0].account.parts, vec!["Trading"]);
+    }
+
+    #[test]
+    fn validate_iter_is_lazy() {
+        let ledger = Ledger::builder()
+            .directives(vec![sale("USD"), sale("EUR"), sale("CAD"), sale("USD")])
+            .build();
+        assert_eq!(ledger.validate_iter().count(), 2);
+
+        let checked = Cell::new(0);
+        let first = validate_directives(
+            ledger
+                .directives
+                .iter()
+                .inspect(|_| checked.set(checked.get() + 1)),
+        )
+        .next();
+        match first {
+            Some(ValidationError::PostingCurrency(err)) => assert_eq!(err.price_currency, "EUR"),
+            other => panic!("expected a posting currency error, got {:?}", other),
+        }
+        assert_eq!(checked.get(), 2);
+    }
+
+    #[test]
+    fn duplicate_open() {
+        let ledger = Ledger::builder()
+            .directives(vec![
+                open("2020-01-01", assets("Checking")),
+                open("2020-01-01", assets("Savings")),
+                open("2020-03-01", assets("Checking")),
+            ])
+            .build();
+        let errors = check_duplicate_opens(&ledger);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].account.parts, vec!["Checking"]);
+        assert_eq!(errors[0].first_date, Date::from_str_unchecked("2020-01-01"));
+        assert_eq!(
+            errors[0].duplicate_date,
+            Date::from_str_unchecked("2020-03-01")
+        );
+
+        let errors: Vec<_> = ledger.validate_iter().collect();
+        assert_eq!(
+            errors,
+            vec![ValidationError::DuplicateOpen(
+                check_duplicate_opens(&ledger).remove(0)
+            )]
+        );
+    }
+
+    fn posting_on(
+        date: &'static str,
+        name: &'static str,
+        currency: &'static str,
+    ) -> Directive<'static> {
+        Directive::Transaction(transaction(date, vec![posting(assets(name), 1, currency)]))
+    }
+
+    #[test]
+    fn posting_after_close() {
+        let ledger = Ledger::builder()
+            .directives(vec![
+                posting_on("2020-03-02", "Checking", "USD"),
+                open("2020-01-01", assets("Checking")),
+                posting_on("2020-03-01", "Checking", "USD"),
+                Directive::Close(
+                    Close::builder()
+                        .date(Date::from_str_unchecked("2020-03-01"))
+                        .account(assets("Checking"))
+                        .build(),
+                ),
+                posting_on("2019-12-31", "Checking", "USD"),
+                posting_on("2020-02-01", "Savings", "USD"),
+            ])
+            .build();
+        let errors = check_account_lifecycle(&ledger);
+        let reasons: Vec<_> = errors.iter().map(|err| err.reason.clone()).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                LifecycleViolation::BeforeOpen(Date::from_str_unchecked("2020-01-01")),
+                LifecycleViolation::Unopened,
+                LifecycleViolation::AfterClose(Date::from_str_unchecked("2020-03-01")),
+            ]
+        );
+        assert_eq!(errors[2].date, Date::from_str_unchecked("2020-03-02"));
+        assert_eq!(errors[2].account.parts, vec!["Checking"]);
+    }
+
+    #[test]
+    fn currency_constraint() {
+        let mut checking = open("2020-01-01", assets("Checking"));
+        if let Directive::Open(open) = &mut checking {
+            open.currencies = vec!["USD".into(), "CAD".into()];
+        }
+        let ledger = Ledger::builder()
+            .directives(vec![
+                checking,
+                open("2020-01-01", assets("Savings")),
+                posting_on("2020-01-02", "Checking", "USD"),
+                posting_on("2020-01-02", "Checking", "EUR"),
+                posting_on("2020-01-02", "Savings", "EUR"),
+            ])
+            .build();
+        let errors = check_account_lifecycle(&ledger);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].account.parts, vec!["Checking"]);
+        assert_eq!(errors[0].reason, LifecycleViolation::Currency("EUR".into()));
+    }
+}
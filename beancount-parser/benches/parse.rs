@@ -0,0 +1,75 @@
+use std::fmt::Write;
+
+use beancount_parser::parse;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+/// A ledger of `count` transactions with two postings each, transaction and posting metadata,
+/// tags, costs and prices.
+fn transactions(count: usize) -> String {
+    let mut ledger = String::from(
+        "2000-01-01 open Assets:Cash USD\n\
+         2000-01-01 open Assets:Brokerage HOOL\n\
+         2000-01-01 open Expenses:Food USD\n\n",
+    );
+    for i in 0..count {
+        let date = format!("2001-{:02}-{:02}", i % 12 + 1, i % 28 + 1);
+        if i % 2 == 0 {
+            writeln!(
+                ledger,
+                "{} * \"Cafe {}\" \"Lunch\" #food\n  receipt: \"{}.pdf\"\n  \
+                 Expenses:Food  {}.{:02} USD\n    category: \"meal\"\n  Assets:Cash\n",
+                date,
+                i,
+                i,
+                i % 100,
+                i % 97
+            )
+        } else {
+            writeln!(
+                ledger,
+                "{} * \"Buy\" ^trade-{}\n  Assets:Brokerage  {} HOOL {{{}.25 USD}} @ {}.50 USD\n  \
+                 Assets:Cash  -{}.50 USD\n",
+                date,
+                i,
+                i % 10 + 1,
+                i % 500,
+                i % 500,
+                (i % 10 + 1) * (i % 500)
+            )
+        }
+        .unwrap();
+    }
+    ledger
+}
+
+/// A ledger whose amounts are arithmetic expressions, to exercise the number expression parser.
+fn expressions(count: usize) -> String {
+    let mut ledger = String::from("2000-01-01 open Assets:Cash USD\n\n");
+    for i in 0..count {
+        writeln!(
+            ledger,
+            "2001-01-01 * \"Split\"\n  Expenses:Shared  ({} + 12.50) * 3 / 4 - -{} USD\n  \
+             Assets:Cash  -(({} * 2) / (1 + 1)) USD\n",
+            i,
+            i % 7,
+            i
+        )
+        .unwrap();
+    }
+    ledger
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for (name, ledger) in [
+        ("transactions", transactions(10_000)),
+        ("expressions", expressions(10_000)),
+    ] {
+        group.throughput(Throughput::Bytes(ledger.len() as u64));
+        group.bench_function(name, |b| b.iter(|| parse(black_box(&ledger)).unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -1,5 +1,7 @@
 use std::error::Error;
 use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
 
 use pest::Span;
 
@@ -15,6 +17,8 @@ pub enum ParseErrorKind {
     InvalidInput { message: String },
     /// Parser has reached an invalid state (most likely a bug in the parser).
     InvalidParserState { message: String },
+    /// A file could not be read.
+    Io { message: String },
 }
 
 #[derive(Debug)]
@@ -23,6 +27,8 @@ pub struct ParseError {
     pub kind: ParseErrorKind,
     /// The (line, column) location of the error in the input.
     pub location: (usize, usize),
+    /// The file the error occurred in, when parsing from the filesystem.
+    pub file: Option<PathBuf>,
     source: Option<Box<dyn Error + 'static + Send + Sync>>,
 }
 
@@ -38,8 +44,17 @@ impl fmt::Display for ParseError {
             ParseErrorKind::InvalidParserState { message } => {
                 write!(f, "Parser has reached an invalid state (please report this as a bug): expected {}", message)?;
             }
+            ParseErrorKind::Io { message } => {
+                write!(f, "{}", message)?;
+            }
+        }
+        if self.location != (0, 0) {
+            write!(f, " at line {} column {}", self.location.0, self.location.1)?;
         }
-        write!(f, " at line {} column {}", self.location.0, self.location.1)
+        if let Some(file) = &self.file {
+            write!(f, " in {}", file.display())?;
+        }
+        Ok(())
     }
 }
 
@@ -51,6 +66,34 @@ impl Error for ParseError {
     }
 }
 
+/// A problem in the input that was recovered from instead of failing the parse.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseWarning {
+    /// Description of the problem and how it was handled.
+    pub message: String,
+    /// The (line, column) location of the problem in the input.
+    pub location: (usize, usize),
+}
+
+impl ParseWarning {
+    pub(crate) fn with_span<T: ToString>(msg: T, span: Span) -> ParseWarning {
+        ParseWarning {
+            message: msg.to_string(),
+            location: span.start_pos().line_col(),
+        }
+    }
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.message, self.location.0, self.location.1
+        )
+    }
+}
+
 impl ParseError {
     pub(crate) fn invalid_input_with_span<T: ToString>(msg: T, span: Span) -> ParseError {
         ParseError {
@@ -59,7 +102,39 @@ impl ParseError {
             },
             location: span.start_pos().line_col(),
             source: None,
+            file: None,
+        }
+    }
+
+    pub(crate) fn invalid_input_at<T: ToString>(msg: T, location: (usize, usize)) -> ParseError {
+        ParseError {
+            kind: ParseErrorKind::InvalidInput {
+                message: msg.to_string(),
+            },
+            location,
+            source: None,
+            file: None,
+        }
+    }
+
+    /// An error reading `what`, e.g. the path of a file.
+    pub(crate) fn io<T: fmt::Display>(err: io::Error, what: T) -> ParseError {
+        ParseError {
+            kind: ParseErrorKind::Io {
+                message: format!("could not read {}: {}", what, err),
+            },
+            location: (0, 0),
+            source: Some(Box::new(err)),
+            file: None,
+        }
+    }
+
+    /// Records the file the error occurred in, unless an included file was already recorded.
+    pub(crate) fn in_file(mut self, path: &Path) -> ParseError {
+        if self.file.is_none() {
+            self.file = Some(path.to_path_buf());
         }
+        self
     }
 
     pub(crate) fn invalid_state<T: ToString>(msg: T) -> ParseError {
@@ -69,6 +144,7 @@ impl ParseError {
             },
             location: (0, 0),
             source: None,
+            file: None,
         }
     }
 
@@ -79,6 +155,7 @@ impl ParseError {
             },
             location: span.start_pos().line_col(),
             source: None,
+            file: None,
         }
     }
 
@@ -86,7 +163,7 @@ impl ParseError {
         let message = format!("error while parsing number: {}", err);
         let pest_error = pest::error::Error::new_from_span(
             pest::error::ErrorVariant::<Rule>::CustomError { message },
-            span.clone(),
+            span,
         );
         ParseError {
             kind: ParseErrorKind::DecimalError {
@@ -94,6 +171,7 @@ impl ParseError {
             },
             location: span.start_pos().line_col(),
             source: Some(Box::new(err)),
+            file: None,
         }
     }
 }
@@ -121,6 +199,7 @@ impl From<pest::error::Error<Rule>> for ParseError {
                 Rule::date => "date",
                 Rule::num => "number",
                 Rule::int => "integer",
+                Rule::exponent => "exponent",
                 Rule::separated_int => "integer with separators",
                 Rule::num_infix => "numeric infix operator",
                 Rule::num_prefix => "numeric prefix operator",
@@ -143,6 +222,8 @@ impl From<pest::error::Error<Rule>> for ParseError {
                 Rule::account_type => "an account category (first part of account name)",
                 Rule::account_name_piece => "part of an account name",
                 Rule::account => "an account name",
+                Rule::account_wildcard => "an account name followed by ':*'",
+                Rule::balance_amount => "balance amount",
                 Rule::tag_name => "tag name",
                 Rule::link => "link",
                 Rule::tag => "tag",
@@ -178,9 +259,11 @@ impl From<pest::error::Error<Rule>> for ParseError {
                 Rule::flag_returns => "'R'",
                 Rule::flag_merging => "'M'",
                 Rule::flag_forecasted => "'#'",
+                Rule::flag_other => "'&', '?' or '%'",
                 Rule::txn_strings => "payee and narration strings",
                 Rule::posting => "posting",
                 Rule::posting_or_kv_list => "posting or metadata",
+                Rule::txn_indent => "indentation",
                 Rule::indented_posting_or_kv_list => "indented posting or metadata",
                 Rule::eol_posting_or_kv_list => "newline followed by indented posting or metadata",
                 Rule::price_annotation => "price annotation",
@@ -207,6 +290,7 @@ impl From<pest::error::Error<Rule>> for ParseError {
             },
             location,
             source: Some(Box::new(err)),
+            file: None,
         }
     }
 }
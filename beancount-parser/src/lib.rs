@@ -1,6 +1,10 @@
 use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use lazy_static::lazy_static;
@@ -11,8 +15,12 @@ use pest_derive::Parser as PestParser;
 use rust_decimal::Decimal;
 
 use beancount_core as bc;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "unicode-normalization")]
+use unicode_normalization::{is_nfc, UnicodeNormalization};
 
-use error::{ParseError, ParseResult};
+use error::{ParseError, ParseResult, ParseWarning};
 
 pub mod error;
 
@@ -91,8 +99,38 @@ lazy_static! {
 #[grammar = "beancount.pest"]
 pub struct BeancountParser;
 
+/// Options controlling how the input is parsed.
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    /// Normalize account names to Unicode Normalization Form C, so that an account spelled with
+    /// precomposed characters (`é`) equals one spelled with combining marks (`e` + U+0301).
+    /// Commodities are restricted to ASCII by the grammar and need no normalization.
+    #[cfg(feature = "unicode-normalization")]
+    pub normalize_unicode: bool,
+
+    /// Accept metadata keys starting with an uppercase letter, lowercasing the letter and
+    /// recording a warning instead of failing the parse. Warnings are returned by
+    /// [`parse_with_warnings`](fn.parse_with_warnings.html).
+    pub lenient_meta_keys: bool,
+
+    /// Round the result of each division in a cost number (`{1 / 3 USD}`) to this many decimal
+    /// places. Lots often need more precision than amounts are displayed with; by default
+    /// divisions keep the full precision of `Decimal`.
+    pub cost_division_precision: Option<u32>,
+
+    /// Accept dates that don't exist in the calendar, such as `2020-02-30`, instead of failing
+    /// the parse.
+    pub lenient_dates: bool,
+
+    /// Keep each number of an amount or cost as written (`1,234.50`), in the `raw` fields of
+    /// `Amount`, `IncompleteAmount` and `CostSpec`, so that it can be rendered back unchanged.
+    pub keep_raw_numbers: bool,
+}
+
 #[derive(Debug)]
 struct ParseState<'i> {
+    options: ParseOptions,
+
     root_names: HashMap<bc::AccountType, String>,
 
     // Track pushed tag count with HashMap<&str, u64> instead of only tracking
@@ -100,17 +138,20 @@ struct ParseState<'i> {
     // same tag, and conformance with bean-check requires an equal number of
     // pops.
     pushed_tags: HashMap<&'i str, u16>,
+
+    warnings: RefCell<Vec<ParseWarning>>,
 }
 
 impl<'i> ParseState<'i> {
     fn new() -> Self {
-        use bc::AccountType::*;
         ParseState {
-            root_names: [Assets, Liabilities, Equity, Income, Expenses]
-                .iter()
-                .map(|ty| (*ty, ty.default_name().to_string()))
+            options: ParseOptions::default(),
+            root_names: bc::AccountType::all()
+                .into_iter()
+                .map(|ty| (ty, ty.default_name().to_string()))
                 .collect(),
             pushed_tags: HashMap::new(),
+            warnings: RefCell::new(Vec::new()),
         }
     }
 
@@ -132,8 +173,22 @@ impl<'i> ParseState<'i> {
         }
     }
 
-    fn get_pushed_tags(&self) -> impl Iterator<Item = &&str> {
-        self.pushed_tags.keys()
+    fn get_pushed_tags(&self) -> impl Iterator<Item = &'i str> + '_ {
+        self.pushed_tags.keys().copied()
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    fn normalize<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        if self.options.normalize_unicode && !is_nfc(s) {
+            Cow::Owned(s.nfc().collect())
+        } else {
+            Cow::Borrowed(s)
+        }
+    }
+
+    #[cfg(not(feature = "unicode-normalization"))]
+    fn normalize<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        Cow::Borrowed(s)
     }
 }
 
@@ -145,55 +200,306 @@ fn optional_rule<'i>(rule: Rule, pairs: &mut Pairs<'i, Rule>) -> Option<Pair<'i,
 }
 
 pub fn parse<'i>(input: &'i str) -> ParseResult<bc::Ledger<'i>> {
-    let parsed = BeancountParser::parse(Rule::file, &input)?
-        .next()
-        .ok_or_else(|| ParseError::invalid_state("non-empty parse result"))?;
+    parse_with_options(input, &ParseOptions::default())
+}
 
-    let mut state = ParseState::new();
-    let mut directives = Vec::new();
+pub fn parse_with_options<'i>(
+    input: &'i str,
+    options: &ParseOptions,
+) -> ParseResult<bc::Ledger<'i>> {
+    parse_with_warnings(input, options).map(|(ledger, _)| ledger)
+}
 
-    for directive_pair in parsed.into_inner() {
-        match directive_pair.as_rule() {
-            Rule::EOI => {
-                let pushed_tags = state
-                    .get_pushed_tags()
-                    .map(|s| format!("'{}'", s))
-                    .collect::<Vec<String>>()
-                    .join(", ");
-                if !pushed_tags.is_empty() {
-                    return Err(ParseError::invalid_input_with_span(
-                        format!("Unbalanced pushed tag(s): {}", pushed_tags),
-                        directive_pair.as_span(),
-                    ));
-                }
-                break;
+/// Parses the input like [`parse_with_options`](fn.parse_with_options.html), also returning the
+/// warnings for problems that were recovered from.
+pub fn parse_with_warnings<'i>(
+    input: &'i str,
+    options: &ParseOptions,
+) -> ParseResult<(bc::Ledger<'i>, Vec<ParseWarning>)> {
+    parse_input(input, options).map(|parsed| (parsed.ledger, parsed.warnings))
+}
+
+/// The position of a directive in the parsed input, counting lines and columns from 1.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Parses the input like [`parse_with_options`](fn.parse_with_options.html), also returning the
+/// location where each directive starts. The locations are in the same order as
+/// `ledger.directives`.
+pub fn parse_with_locations<'i>(
+    input: &'i str,
+    options: &ParseOptions,
+) -> ParseResult<(bc::Ledger<'i>, Vec<Location>)> {
+    parse_input(input, options).map(|parsed| (parsed.ledger, parsed.locations))
+}
+
+/// Reads everything from `reader` and parses it, returning a ledger that doesn't borrow the
+/// input. The whole input is read before parsing starts.
+///
+/// # Example
+/// ```rust,no_run
+/// let ledger = beancount_parser::parse_reader(std::io::stdin().lock()).unwrap();
+/// println!("{} directives", ledger.directives.len());
+/// ```
+pub fn parse_reader<R: Read>(mut reader: R) -> ParseResult<bc::Ledger<'static>> {
+    let mut input = String::new();
+    reader
+        .read_to_string(&mut input)
+        .map_err(|err| ParseError::io(err, "input"))?;
+    parse(&input).map(bc::Ledger::into_owned)
+}
+
+/// Reads and parses the file at `path`, replacing each `include` directive with the directives of
+/// the included file. Included paths are resolved relative to the including file.
+///
+/// Include cycles are an error; the error's `file` is the file containing the offending
+/// `include`.
+pub fn parse_file_recursive(path: &Path) -> ParseResult<bc::Ledger<'static>> {
+    let directives = parse_file_directives(path, &mut Vec::new())?;
+    Ok(bc::Ledger::builder().directives(directives).build())
+}
+
+/// Reads and parses each of the files at `paths` on rayon's thread pool. The results are in the
+/// same order as `paths`; a file that can't be read or parsed doesn't stop the others.
+///
+/// Unlike [`parse_file_recursive`](fn.parse_file_recursive.html), `include` directives are kept
+/// as they are.
+#[cfg(feature = "rayon")]
+pub fn parse_files_parallel(paths: &[PathBuf]) -> Vec<ParseResult<bc::Ledger<'static>>> {
+    paths
+        .par_iter()
+        .map(|path| {
+            fs::read_to_string(path)
+                .map_err(|err| ParseError::io(err, path.display()))
+                .and_then(|input| parse(&input).map(bc::Ledger::into_owned))
+                .map_err(|err| err.in_file(path))
+        })
+        .collect()
+}
+
+fn parse_file_directives(
+    path: &Path,
+    including: &mut Vec<PathBuf>,
+) -> ParseResult<Vec<bc::Directive<'static>>> {
+    let input = fs::read_to_string(path).map_err(|err| ParseError::io(err, path.display()))?;
+    let canonical = path
+        .canonicalize()
+        .map_err(|err| ParseError::io(err, path.display()))?;
+    let (ledger, locations) =
+        parse_with_locations(&input, &ParseOptions::default()).map_err(|err| err.in_file(path))?;
+
+    including.push(canonical);
+    let mut directives = Vec::with_capacity(ledger.directives.len());
+    for (directive, location) in ledger.directives.into_iter().zip(locations) {
+        let include = match directive {
+            bc::Directive::Include(include) => include,
+            directive => {
+                directives.push(directive.into_owned());
+                continue;
             }
-            Rule::pushtag => {
-                state.push_tag(extract_tag(directive_pair)?);
+        };
+        let included = path
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(&*include.filename);
+        let location = (location.line, location.column);
+        if let Ok(canonical) = included.canonicalize() {
+            if including.contains(&canonical) {
+                return Err(ParseError::invalid_input_at(
+                    format!(
+                        "include cycle: {} is already being parsed",
+                        included.display()
+                    ),
+                    location,
+                )
+                .in_file(path));
             }
-            Rule::poptag => {
-                let span = directive_pair.as_span();
-                if let Err(msg) = state.pop_tag(extract_tag(directive_pair)?) {
-                    return Err(ParseError::invalid_input_with_span(msg, span));
-                }
+        }
+        let nested = parse_file_directives(&included, including).map_err(|mut err| {
+            if err.file.is_none() {
+                err.location = location;
             }
-            _ => {
-                let dir = directive(directive_pair, &state)?;
-
-                // Change the root account names on such an option:
-                // option "name_assets" "Assets"
-                if let bc::Directive::Option(ref opt) = dir {
-                    if let Some((account_type, account_name)) = opt.root_name_change() {
-                        state.root_names.insert(account_type, account_name);
+            err.in_file(path)
+        })?;
+        directives.extend(nested);
+    }
+    including.pop();
+    Ok(directives)
+}
+
+/// Everything produced by parsing an input.
+struct Parsed<'i> {
+    ledger: bc::Ledger<'i>,
+    locations: Vec<Location>,
+    warnings: Vec<ParseWarning>,
+}
+
+fn parse_input<'i>(input: &'i str, options: &ParseOptions) -> ParseResult<Parsed<'i>> {
+    let mut iter = DirectiveIter::new(input, options);
+    let mut directives = Vec::new();
+    let mut locations = Vec::new();
+    for item in &mut iter {
+        let (directive, location) = item?;
+        directives.push(directive);
+        locations.push(location);
+    }
+
+    Ok(Parsed {
+        ledger: bc::Ledger::builder().directives(directives).build(),
+        locations,
+        warnings: iter.state.warnings.into_inner(),
+    })
+}
+
+/// Parses the input one directive at a time, yielding each directive as soon as it is
+/// converted. Iteration can be stopped early, in which case the remaining directives are never
+/// converted; `pushtag` and `option` directives still apply to the directives that follow them.
+///
+/// The input is checked against the grammar as a whole before the first directive is yielded,
+/// so a syntax error anywhere in the input is returned as the only item. Iteration ends after
+/// the first error.
+pub fn parse_iter(input: &str) -> impl Iterator<Item = ParseResult<bc::Directive<'_>>> {
+    DirectiveIter::new(input, &ParseOptions::default()).map(|item| item.map(|(d, _)| d))
+}
+
+/// Converts the directives of a parsed input in order, threading the parse state through them.
+struct DirectiveIter<'i> {
+    input: &'i str,
+    pairs: Option<Pairs<'i, Rule>>,
+    error: Option<ParseError>,
+    state: ParseState<'i>,
+    last_end: usize,
+}
+
+impl<'i> DirectiveIter<'i> {
+    fn new(input: &'i str, options: &ParseOptions) -> Self {
+        let parsed = BeancountParser::parse(Rule::file, input)
+            .map_err(ParseError::from)
+            .and_then(|mut pairs| {
+                pairs
+                    .next()
+                    .ok_or_else(|| ParseError::invalid_state("non-empty parse result"))
+            });
+        let mut state = ParseState::new();
+        state.options = options.clone();
+        let (pairs, error) = match parsed {
+            Ok(file) => (Some(file.into_inner()), None),
+            Err(err) => (None, Some(err)),
+        };
+        DirectiveIter {
+            input,
+            pairs,
+            error,
+            state,
+            last_end: 0,
+        }
+    }
+
+    fn next_directive(&mut self) -> ParseResult<Option<(bc::Directive<'i>, Location)>> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        let pairs = match &mut self.pairs {
+            Some(pairs) => pairs,
+            None => return Ok(None),
+        };
+        let state = &mut self.state;
+
+        for directive_pair in pairs {
+            let span = directive_pair.as_span();
+            let blank_lines = count_blank_lines(&self.input[self.last_end..span.start()]);
+            self.last_end = span.end();
+            match directive_pair.as_rule() {
+                Rule::EOI => {
+                    let pushed_tags = state
+                        .get_pushed_tags()
+                        .map(|s| format!("'{}'", s))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    if !pushed_tags.is_empty() {
+                        return Err(ParseError::invalid_input_with_span(
+                            format!("Unbalanced pushed tag(s): {}", pushed_tags),
+                            directive_pair.as_span(),
+                        ));
+                    }
+                    break;
+                }
+                Rule::pushtag => {
+                    state.push_tag(extract_tag(directive_pair)?);
+                }
+                Rule::poptag => {
+                    let span = directive_pair.as_span();
+                    if let Err(msg) = state.pop_tag(extract_tag(directive_pair)?) {
+                        return Err(ParseError::invalid_input_with_span(msg, span));
                     }
                 }
+                _ => {
+                    let mut dir = directive(directive_pair, state)?;
+                    set_blank_lines_before(&mut dir, blank_lines);
+
+                    // Change the root account names on such an option:
+                    // option "name_assets" "Assets"
+                    if let bc::Directive::Option(ref opt) = dir {
+                        if let Some((account_type, account_name)) = opt.root_name_change() {
+                            let account_name = state.normalize(&account_name).into_owned();
+                            state.root_names.insert(account_type, account_name);
+                        }
+                    }
 
-                directives.push(dir);
+                    let (line, column) = span.start_pos().line_col();
+                    return Ok(Some((dir, Location { line, column })));
+                }
             }
         }
+        Ok(None)
     }
+}
 
-    Ok(bc::Ledger::builder().directives(directives).build())
+impl<'i> Iterator for DirectiveIter<'i> {
+    type Item = ParseResult<(bc::Directive<'i>, Location)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.next_directive().transpose();
+        if !matches!(item, Some(Ok(_))) {
+            self.pairs = None;
+        }
+        item
+    }
+}
+
+/// Counts the whitespace-only lines in the gap between two directives. Comment lines are not
+/// counted as blank.
+fn count_blank_lines(gap: &str) -> u8 {
+    let count = gap
+        .split_inclusive('\n')
+        .filter(|line| line.ends_with('\n') && line.trim().is_empty())
+        .count();
+    u8::try_from(count).unwrap_or(u8::MAX)
+}
+
+fn set_blank_lines_before(directive: &mut bc::Directive<'_>, blank_lines: u8) {
+    use bc::Directive::*;
+    match directive {
+        Open(d) => d.blank_lines_before = blank_lines,
+        Close(d) => d.blank_lines_before = blank_lines,
+        Balance(d) => d.blank_lines_before = blank_lines,
+        Option(d) => d.blank_lines_before = blank_lines,
+        Commodity(d) => d.blank_lines_before = blank_lines,
+        Custom(d) => d.blank_lines_before = blank_lines,
+        Document(d) => d.blank_lines_before = blank_lines,
+        Event(d) => d.blank_lines_before = blank_lines,
+        Include(d) => d.blank_lines_before = blank_lines,
+        Note(d) => d.blank_lines_before = blank_lines,
+        Pad(d) => d.blank_lines_before = blank_lines,
+        Plugin(d) => d.blank_lines_before = blank_lines,
+        Price(d) => d.blank_lines_before = blank_lines,
+        Query(d) => d.blank_lines_before = blank_lines,
+        Transaction(d) => d.blank_lines_before = blank_lines,
+        Unsupported(_) => {}
+    }
 }
 
 fn extract_tag<'i>(pair: Pair<'i, Rule>) -> ParseResult<&'i str> {
@@ -204,7 +510,10 @@ fn extract_tag<'i>(pair: Pair<'i, Rule>) -> ParseResult<&'i str> {
     Ok(&pair.as_str()[1..])
 }
 
-fn directive<'i>(directive: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Directive<'i>> {
+fn directive<'i>(
+    directive: Pair<'i, Rule>,
+    state: &ParseState<'i>,
+) -> ParseResult<bc::Directive<'i>> {
     let dir = match directive.as_rule() {
         Rule::option => option_directive(directive)?,
         Rule::plugin => plugin_directive(directive)?,
@@ -212,6 +521,7 @@ fn directive<'i>(directive: Pair<'i, Rule>, state: &ParseState) -> ParseResult<b
         Rule::include => include_directive(directive)?,
         Rule::open => open_directive(directive, state)?,
         Rule::close => close_directive(directive, state)?,
+        Rule::balance => balance_directive(directive, state)?,
         Rule::commodity_directive => commodity_directive(directive, state)?,
         Rule::note => note_directive(directive, state)?,
         Rule::pad => pad_directive(directive, state)?,
@@ -220,7 +530,7 @@ fn directive<'i>(directive: Pair<'i, Rule>, state: &ParseState) -> ParseResult<b
         Rule::document => document_directive(directive, state)?,
         Rule::price => price_directive(directive, state)?,
         Rule::transaction => transaction_directive(directive, state)?,
-        _ => bc::Directive::Unsupported,
+        _ => bc::Directive::Unsupported(directive.as_str().into()),
     };
     Ok(dir)
 }
@@ -254,11 +564,11 @@ fn custom_directive<'i>(
     let source = directive.as_str();
     Ok(bc::Directive::Custom(construct! {
         bc::Custom: directive => {
-            date = date;
+            date = |p| date(p, state);
             name = get_quoted_str;
             args = if Rule::custom_value_list {
-                |p: Pair<'i, _>| -> ParseResult<Vec<Cow<'i, str>>> {
-                    p.into_inner().map(get_quoted_str).collect()
+                |p: Pair<'i, _>| -> ParseResult<Vec<bc::metadata::MetaValue<'i>>> {
+                    p.into_inner().map(|p| meta_value(p, state)).collect()
                 }
             } else {
                 Vec::new()
@@ -286,7 +596,7 @@ fn open_directive<'i>(
     let source = directive.as_str();
     Ok(bc::Directive::Open(construct! {
         bc::Open: directive => {
-            date = date;
+            date = |p| date(p, state);
             account = |p| account(p, state);
             currencies = if Rule::commodity_list {
                 |p: Pair<'i, _>| -> ParseResult<Vec<_>> {
@@ -300,8 +610,9 @@ fn open_directive<'i>(
             booking = if Rule::quoted_str {
                 |p: Pair<'i, _>| -> ParseResult<Option<bc::Booking>> {
                     let span = p.as_span();
-                    bc::Booking::try_from(get_quoted_str(p)?.as_ref())
-                        .map_err(|_| ParseError::invalid_input_with_span(format!("unknown booking method {}", span.as_str()), span))
+                    get_quoted_str(p)?
+                        .parse::<bc::Booking>()
+                        .map_err(|e| ParseError::invalid_input_with_span(e, span))
                         .map(Some)
                 }
             } else {
@@ -320,7 +631,7 @@ fn close_directive<'i>(
     let source = directive.as_str();
     Ok(bc::Directive::Close(construct! {
         bc::Close: directive => {
-            date = date;
+            date = |p| date(p, state);
             account = |p| account(p, state);
             meta = |p| meta_kv(p, state);
             source := Some(source);
@@ -328,6 +639,92 @@ fn close_directive<'i>(
     }))
 }
 
+fn balance_directive<'i>(
+    directive: Pair<'i, Rule>,
+    state: &ParseState,
+) -> ParseResult<bc::Directive<'i>> {
+    let source = directive.as_str();
+    Ok(bc::Directive::Balance(construct! {
+        bc::Balance: directive => {
+            date = |p| date(p, state);
+            let (account, inclusive) = from pair {
+                balance_account(pair, state)?
+            };
+            account := account;
+            inclusive := inclusive;
+            let (amount, tolerance) = from pair {
+                balance_amount(pair, state)?
+            };
+            amount := amount;
+            tolerance := tolerance;
+            meta = |p| meta_kv(p, state);
+            source := Some(source);
+        }
+    }))
+}
+
+/// The amount of a balance directive and its explicit tolerance, if any.
+fn balance_amount<'i>(
+    pair: Pair<'i, Rule>,
+    state: &ParseState,
+) -> ParseResult<(bc::Amount<'i>, Option<Decimal>)> {
+    debug_assert!(pair.as_rule() == Rule::balance_amount);
+    let span = pair.as_span();
+    let mut inner = pair.into_inner();
+    let num_pair = inner
+        .next()
+        .ok_or_else(|| ParseError::invalid_state_with_span("balance number", span))?;
+    let raw = raw_number(&num_pair, state);
+    let num = num_expr(num_pair)?;
+    let tolerance = optional_rule(Rule::num_expr, &mut inner)
+        .map(num_expr)
+        .transpose()?;
+    let currency = inner
+        .next()
+        .ok_or_else(|| ParseError::invalid_state_with_span("balance commodity", span))?
+        .as_str();
+    let amount = bc::Amount::builder()
+        .num(num)
+        .currency(currency.into())
+        .raw(raw)
+        .build();
+    Ok((amount, tolerance))
+}
+
+/// The account of a balance directive, and whether it is followed by the `:*` sub-account
+/// wildcard.
+fn balance_account<'i>(
+    pair: Pair<'i, Rule>,
+    state: &ParseState,
+) -> ParseResult<(bc::Account<'i>, bool)> {
+    match pair.as_rule() {
+        Rule::account => Ok((account(pair, state)?, false)),
+        Rule::account_wildcard => wildcard_account(pair, state).map(|account| (account, true)),
+        _ => Err(ParseError::invalid_state_with_span(
+            "account",
+            pair.as_span(),
+        )),
+    }
+}
+
+#[cfg(feature = "balance-wildcard")]
+fn wildcard_account<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Account<'i>> {
+    let span = pair.as_span();
+    let account_pair = pair
+        .into_inner()
+        .next()
+        .ok_or_else(|| ParseError::invalid_state_with_span("account", span))?;
+    account(account_pair, state)
+}
+
+#[cfg(not(feature = "balance-wildcard"))]
+fn wildcard_account<'i>(pair: Pair<'i, Rule>, _: &ParseState) -> ParseResult<bc::Account<'i>> {
+    Err(ParseError::invalid_input_with_span(
+        "sub-account wildcards in balance directives require the `balance-wildcard` feature",
+        pair.as_span(),
+    ))
+}
+
 fn commodity_directive<'i>(
     directive: Pair<'i, Rule>,
     state: &ParseState,
@@ -335,7 +732,7 @@ fn commodity_directive<'i>(
     let source = directive.as_str();
     Ok(bc::Directive::Commodity(construct! {
         bc::Commodity: directive => {
-            date = date;
+            date = |p| date(p, state);
             name = as_str;
             meta = |p| meta_kv(p, state);
             source := Some(source);
@@ -350,7 +747,7 @@ fn note_directive<'i>(
     let source = directive.as_str();
     Ok(bc::Directive::Note(construct! {
         bc::Note: directive => {
-            date = date;
+            date = |p| date(p, state);
             account = |p| account(p, state);
             comment = as_str;
             meta = |p| meta_kv(p, state);
@@ -366,7 +763,7 @@ fn pad_directive<'i>(
     let source = directive.as_str();
     Ok(bc::Directive::Pad(construct! {
         bc::Pad: directive => {
-            date = date;
+            date = |p| date(p, state);
             pad_to_account = |p| account(p, state);
             pad_from_account = |p| account(p, state);
             meta = |p| meta_kv(p, state);
@@ -382,7 +779,7 @@ fn query_directive<'i>(
     let source = directive.as_str();
     Ok(bc::Directive::Query(construct! {
         bc::Query: directive => {
-            date = date;
+            date = |p| date(p, state);
             name = get_quoted_str;
             query_string = get_quoted_str;
             meta = |p| meta_kv(p, state);
@@ -398,7 +795,7 @@ fn event_directive<'i>(
     let source = directive.as_str();
     Ok(bc::Directive::Event(construct! {
         bc::Event: directive => {
-            date = date;
+            date = |p| date(p, state);
             name = get_quoted_str;
             description = get_quoted_str;
             meta = |p| meta_kv(p, state);
@@ -414,7 +811,7 @@ fn document_directive<'i>(
     let source = directive.as_str();
     Ok(bc::Directive::Document(construct! {
         bc::Document: directive => {
-            date = date;
+            date = |p| date(p, state);
             account = |p| account(p, state);
             path = get_quoted_str;
             let (tags, links) = from pair if Rule::tags_links {
@@ -437,9 +834,9 @@ fn price_directive<'i>(
     let source = directive.as_str();
     Ok(bc::Directive::Price(construct! {
         bc::Price: directive => {
-            date = date;
+            date = |p| date(p, state);
             currency = as_str;
-            amount = amount;
+            amount = |p| amount(p, state);
             meta = |p| meta_kv(p, state);
             source := Some(source);
         }
@@ -448,14 +845,14 @@ fn price_directive<'i>(
 
 fn transaction_directive<'i>(
     directive: Pair<'i, Rule>,
-    state: &ParseState,
+    state: &ParseState<'i>,
 ) -> ParseResult<bc::Directive<'i>> {
     let source = directive.as_str();
     Ok(bc::Directive::Transaction(construct! {
         bc::Transaction: directive => {
-            date = date;
+            date = |p| date(p, state);
             flag = flag;
-            let (payee, narration) = from pair {
+            let (payee, narration) = from pair if Rule::txn_strings {
                 let span = pair.as_span();
                 let mut inner = pair.into_inner();
                 let first = inner.next().map(get_quoted_str)
@@ -467,6 +864,8 @@ fn transaction_directive<'i>(
                 } else {
                     (None, first)
                 }
+            } else {
+                (None, "".into())
             };
             payee := payee;
             narration := narration;
@@ -478,17 +877,26 @@ fn transaction_directive<'i>(
             let (meta, postings) = from pair {
                 let mut postings: Vec<bc::Posting<'i>> = Vec::new();
                 let mut tx_meta = bc::metadata::Meta::new();
+                let mut indent = 0;
+                let mut posting_indent = 0;
                 for p in pair.into_inner() {
                     match p.as_rule() {
+                        Rule::txn_indent => {
+                            indent = indent_width(p.as_str());
+                        }
                         Rule::posting => {
+                            posting_indent = indent;
                             postings.push(posting(p, state)?);
                         }
                         Rule::key_value => {
                             let (k, v) = meta_kv_pair(p, state)?;
-                            if let Some(last) = postings.last_mut() {
-                                last.meta.insert(k, v);
-                            } else {
-                                tx_meta.insert(k, v);
+                            match postings.last_mut() {
+                                Some(last) if indent > posting_indent => {
+                                    last.meta.insert(k, v);
+                                }
+                                _ => {
+                                    tx_meta.insert(k, v);
+                                }
                             }
                         }
                         Rule::tag => {
@@ -499,14 +907,21 @@ fn transaction_directive<'i>(
                             let link = (&p.as_str()[1..]).into();
                             links.insert(link);
                         }
+                        Rule::tags_links => {
+                            let (more_tags, more_links) = tags_links(p)?;
+                            tags.extend(more_tags);
+                            links.extend(more_links);
+                        }
                         rule => {
-                            unimplemented!("rule {:?}", rule);
+                            return Err(ParseError::invalid_state_with_span(
+                                format!("transaction line {:?}", rule),
+                                p.as_span(),
+                            ));
                         }
                     }
                 }
-                for tag in state.get_pushed_tags() {
-                  tags.insert(Cow::from((*tag).to_owned()));
-                }
+                // Pushed tags borrow the input like the rest of the transaction.
+                tags.extend(state.get_pushed_tags().map(Cow::Borrowed));
                 (tx_meta, postings)
             };
             postings := postings;
@@ -518,6 +933,14 @@ fn transaction_directive<'i>(
     }))
 }
 
+/// Width of an indentation in columns, with tabs advancing to the next multiple of 8.
+fn indent_width(indent: &str) -> usize {
+    indent.chars().fold(0, |width, c| match c {
+        '\t' => (width / 8 + 1) * 8,
+        _ => width + 1,
+    })
+}
+
 fn posting<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Posting<'i>> {
     let span = pair.as_span();
     let mut inner = pair.into_inner();
@@ -530,14 +953,14 @@ fn posting<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Post
         .transpose()?
         .ok_or_else(|| ParseError::invalid_state_with_span("account", span))?;
     let units = optional_rule(Rule::incomplete_amount, &mut inner)
-        .map(incomplete_amount)
+        .map(|p| incomplete_amount(p, state))
         .transpose()?
         .unwrap_or_else(|| bc::IncompleteAmount::builder().build());
     let cost = optional_rule(Rule::cost_spec, &mut inner)
-        .map(cost_spec)
+        .map(|p| cost_spec(p, state))
         .transpose()?;
     let price_anno = optional_rule(Rule::price_annotation, &mut inner)
-        .map(price_annotation)
+        .map(|p| price_annotation(p, state))
         .transpose()?;
     let price = match (price_anno, units.num) {
         (Some((true, p)), _) => Some(bc::PriceSpec::Total(p)),
@@ -555,53 +978,103 @@ fn posting<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Post
 }
 
 fn num_expr(pair: Pair<'_, Rule>) -> ParseResult<Decimal> {
+    num_expr_with_precision(pair, None)
+}
+
+/// Evaluates a number expression, rounding the result of each division to `division_precision`
+/// decimal places if given.
+fn num_expr_with_precision(
+    pair: Pair<'_, Rule>,
+    division_precision: Option<u32>,
+) -> ParseResult<Decimal> {
     debug_assert!(pair.as_rule() == Rule::num_expr);
     PRATT_PARSER
         .map_primary(|primary| match primary.as_rule() {
             Rule::num => {
                 let s = primary.as_str().replace(',', "");
-                Decimal::from_str(&s).map_err(|e| ParseError::decimal_parse_error(e, primary.as_span()))
+                if s.contains(['e', 'E']) {
+                    Decimal::from_scientific(&s)
+                } else {
+                    Decimal::from_str(&s)
+                }
+                .map_err(|e| ParseError::decimal_parse_error(e, primary.as_span()))
             }
+            // A parenthesized subexpression.
+            Rule::num_expr => num_expr_with_precision(primary, division_precision),
             _ => unreachable!(),
         })
         .map_prefix(|op, rhs| match op.as_rule() {
-            Rule::neg => rhs.map(|mut v| { v.set_sign_positive(!v.is_sign_positive()); v }),
+            Rule::neg => rhs.map(|mut v| {
+                v.set_sign_positive(!v.is_sign_positive());
+                v
+            }),
             Rule::pos => rhs,
             _ => unreachable!(),
         })
         .map_infix(|lhs, op, rhs| {
             let lhs = lhs?;
             let rhs = rhs?;
-            Ok(match op.as_rule() {
-                Rule::add => lhs + rhs,
-                Rule::subtract => lhs - rhs,
-                Rule::multiply => lhs * rhs,
-                Rule::divide => lhs / rhs,
+            let result = match op.as_rule() {
+                Rule::add => lhs.checked_add(rhs),
+                Rule::subtract => lhs.checked_sub(rhs),
+                Rule::multiply => lhs.checked_mul(rhs),
+                Rule::divide => lhs
+                    .checked_div(rhs)
+                    .map(|quotient| match division_precision {
+                        Some(dp) => quotient.round_dp(dp),
+                        None => quotient,
+                    }),
                 _ => unreachable!(),
+            };
+            // Overflow and division by zero would otherwise panic in `Decimal`'s operators.
+            result.ok_or_else(|| {
+                ParseError::invalid_input_with_span(
+                    format!("cannot evaluate {} {} {}", lhs, op.as_str().trim(), rhs),
+                    op.as_span(),
+                )
             })
         })
         .parse(pair.into_inner())
 }
 
-fn amount<'i>(pair: Pair<'i, Rule>) -> ParseResult<bc::Amount<'i>> {
+/// The text of a number expression, if the options ask for it to be kept.
+fn raw_number<'i>(pair: &Pair<'i, Rule>, state: &ParseState) -> Option<Cow<'i, str>> {
+    if state.options.keep_raw_numbers {
+        // The span of an expression ends after the whitespace that follows it.
+        Some(pair.as_str().trim_end().into())
+    } else {
+        None
+    }
+}
+
+fn amount<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Amount<'i>> {
     debug_assert!(pair.as_rule() == Rule::amount);
     Ok(construct! {
         bc::Amount: pair => {
-            num = num_expr;
+            let (num, raw) = from p {
+                (num_expr(p.clone())?, raw_number(&p, state))
+            };
+            num := num;
+            raw := raw;
             currency = as_str;
         }
     })
 }
 
-fn incomplete_amount<'i>(pair: Pair<'i, Rule>) -> ParseResult<bc::IncompleteAmount<'i>> {
+fn incomplete_amount<'i>(
+    pair: Pair<'i, Rule>,
+    state: &ParseState,
+) -> ParseResult<bc::IncompleteAmount<'i>> {
     debug_assert!(pair.as_rule() == Rule::incomplete_amount);
     Ok(construct! {
         bc::IncompleteAmount: pair => {
-            num = if Rule::num_expr {
-                |p| num_expr(p).map(Some)
+            let (num, raw) = from p if Rule::num_expr {
+                (Some(num_expr(p.clone())?), raw_number(&p, state))
             } else {
-                None
+                (None, None)
             };
+            num := num;
+            raw := raw;
             currency = if Rule::commodity {
                 |p| as_str(p).map(|s| Some(s.into()))
             } else {
@@ -611,9 +1084,9 @@ fn incomplete_amount<'i>(pair: Pair<'i, Rule>) -> ParseResult<bc::IncompleteAmou
     })
 }
 
-fn cost_spec<'i>(pair: Pair<'i, Rule>) -> ParseResult<bc::CostSpec<'i>> {
+fn cost_spec<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::CostSpec<'i>> {
     debug_assert!(pair.as_rule() == Rule::cost_spec);
-    let mut amount = (None, None, None);
+    let mut amount = CompoundAmount::default();
     let mut date_ = None;
     let mut label = None;
     let mut merge = false;
@@ -625,50 +1098,78 @@ fn cost_spec<'i>(pair: Pair<'i, Rule>) -> ParseResult<bc::CostSpec<'i>> {
     let typ = inner.as_rule();
     for p in inner.into_inner() {
         match p.as_rule() {
-            Rule::date => date_ = Some(date(p)?),
+            Rule::date => date_ = Some(date(p, state)?),
             Rule::quoted_str => label = Some(get_quoted_str(p)?),
             Rule::compound_amount => {
-                amount = compound_amount(p)?;
+                amount = compound_amount(p, state)?;
             }
             Rule::asterisk => {
                 merge = true;
             }
-            _ => unimplemented!(),
+            rule => {
+                return Err(ParseError::invalid_state_with_span(
+                    format!("cost component {:?}", rule),
+                    p.as_span(),
+                ));
+            }
         }
     }
     if typ == Rule::cost_spec_total {
-        if amount.1.is_some() {
-            panic!("Per-unit cost may not be specified using total cost");
+        if amount.number_total.is_some() {
+            return Err(ParseError::invalid_input_with_span(
+                "per-unit cost may not be specified using total cost".to_string(),
+                span,
+            ));
         }
-        amount = (None, amount.0, amount.2);
+        amount.number_total = amount.number_per.take();
+        amount.raw_total = amount.raw_per.take();
     }
     Ok(bc::CostSpec::builder()
-        .number_per(amount.0)
-        .number_total(amount.1)
-        .currency(amount.2)
+        .number_per(amount.number_per)
+        .number_total(amount.number_total)
+        .currency(amount.currency)
+        .raw_number_per(amount.raw_per)
+        .raw_number_total(amount.raw_total)
         .date(date_)
         .label(label)
         .merge_cost(merge)
         .build())
 }
 
-fn price_annotation<'i>(pair: Pair<'i, Rule>) -> ParseResult<(bool, bc::IncompleteAmount<'i>)> {
+fn price_annotation<'i>(
+    pair: Pair<'i, Rule>,
+    state: &ParseState,
+) -> ParseResult<(bool, bc::IncompleteAmount<'i>)> {
     debug_assert!(pair.as_rule() == Rule::price_annotation);
     let span = pair.as_span();
     let inner = pair
         .into_inner()
         .next()
-        .ok_or_else(|| ParseError::invalid_state_with_span("price annotation", span.clone()))?;
+        .ok_or_else(|| ParseError::invalid_state_with_span("price annotation", span))?;
     let is_total = inner.as_rule() == Rule::price_annotation_total;
-    let amount = incomplete_amount(
-        inner
-            .into_inner()
-            .next()
-            .ok_or_else(|| ParseError::invalid_state_with_span("incomplete amount", span))?,
-    )?;
+    let amount_pair = inner
+        .into_inner()
+        .next()
+        .ok_or_else(|| ParseError::invalid_state_with_span("incomplete amount", span))?;
+    let amount_span = amount_pair.as_span();
+    let amount = incomplete_amount(amount_pair, state)?;
+    if let Some(num) = amount.num {
+        non_negative(num, "price", amount_span)?;
+    }
     Ok((is_total, amount))
 }
 
+/// Costs and prices are always written unsigned; the sign of a posting is carried by its units.
+fn non_negative(num: Decimal, what: &str, span: pest::Span<'_>) -> ParseResult<Decimal> {
+    if num.is_sign_negative() && !num.is_zero() {
+        return Err(ParseError::invalid_input_with_span(
+            format!("{} {} must not be negative", what, num),
+            span,
+        ));
+    }
+    Ok(num)
+}
+
 fn account<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Account<'i>> {
     debug_assert!(pair.as_rule() == Rule::account);
     let span = pair.as_span();
@@ -676,11 +1177,11 @@ fn account<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Acco
     let first_pair = inner
         .next()
         .ok_or_else(|| ParseError::invalid_state_with_span("first part of account name", span))?;
-    let first = first_pair.as_str();
+    let first = state.normalize(first_pair.as_str());
     let account_type = state
         .root_names
         .iter()
-        .filter(|(_, ref v)| *v == first)
+        .filter(|(_, v)| v.as_str() == first)
         .map(|(k, _)| *k)
         .next()
         .ok_or_else(|| {
@@ -691,7 +1192,7 @@ fn account<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Acco
                 first_pair.as_span(),
             )
         })?;
-    let parts: Vec<_> = inner.map(|p| Cow::Borrowed(&p.as_str()[1..])).collect();
+    let parts: Vec<_> = inner.map(|p| state.normalize(&p.as_str()[1..])).collect();
     Ok(bc::Account::builder().ty(account_type).parts(parts).build())
 }
 
@@ -699,8 +1200,16 @@ fn as_str<'i>(pair: Pair<'i, Rule>) -> ParseResult<&'i str> {
     Ok(pair.as_str())
 }
 
-fn date<'i>(pair: Pair<'i, Rule>) -> ParseResult<bc::Date<'i>> {
-    Ok(bc::Date::from_str_unchecked(pair.as_str()))
+fn date<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Date<'i>> {
+    let date = bc::Date::from_str_unchecked(pair.as_str());
+    // The grammar only bounds the month and day separately, so check the day against the month.
+    if !state.options.lenient_dates && date.year().is_none() {
+        return Err(ParseError::invalid_input_with_span(
+            format!("invalid date {}", pair.as_str()),
+            pair.as_span(),
+        ));
+    }
+    Ok(date)
 }
 
 fn meta_kv<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::metadata::Meta<'i>> {
@@ -726,7 +1235,10 @@ fn tags_links<'i>(
                 links.insert(link);
             }
             rule => {
-                unimplemented!("rule {:?}", rule);
+                return Err(ParseError::invalid_state_with_span(
+                    format!("tag or link {:?}", rule),
+                    p.as_span(),
+                ));
             }
         }
     }
@@ -740,26 +1252,61 @@ fn meta_kv_pair<'i>(
     debug_assert!(pair.as_rule() == Rule::key_value);
     let span = pair.as_span();
     let mut inner = pair.into_inner();
-    let key = inner
+    let key_pair = inner
         .next()
-        .ok_or_else(|| ParseError::invalid_state_with_span("metadata key", span.clone()))?
-        .as_str();
+        .ok_or_else(|| ParseError::invalid_state_with_span("metadata key", span))?;
+    let key = meta_key(key_pair, state)?;
     let value_pair = inner
         .next()
         .and_then(|p| p.into_inner().next())
         .ok_or_else(|| ParseError::invalid_state_with_span("metadata value", span))?;
-    let value = match value_pair.as_rule() {
+    Ok((key, meta_value(value_pair, state)?))
+}
+
+/// Converts a metadata value or custom directive argument.
+fn meta_value<'i>(
+    value_pair: Pair<'i, Rule>,
+    state: &ParseState,
+) -> ParseResult<bc::metadata::MetaValue<'i>> {
+    Ok(match value_pair.as_rule() {
         Rule::quoted_str => bc::metadata::MetaValue::Text(get_quoted_str(value_pair)?),
         Rule::account => bc::metadata::MetaValue::Account(account(value_pair, state)?),
-        Rule::date => bc::metadata::MetaValue::Date(date(value_pair)?),
+        Rule::date => bc::metadata::MetaValue::Date(date(value_pair, state)?),
         Rule::commodity => bc::metadata::MetaValue::Currency(value_pair.as_str().into()),
         Rule::tag => bc::metadata::MetaValue::Tag((&value_pair.as_str()[1..]).into()),
-        Rule::bool => bc::metadata::MetaValue::Bool(value_pair.as_str() == "true"),
-        Rule::amount => bc::metadata::MetaValue::Amount(amount(value_pair)?),
+        Rule::bool => {
+            bc::metadata::MetaValue::Bool(value_pair.as_str().eq_ignore_ascii_case("true"))
+        }
+        Rule::amount => bc::metadata::MetaValue::Amount(amount(value_pair, state)?),
         Rule::num_expr => bc::metadata::MetaValue::Number(num_expr(value_pair)?),
-        _ => unimplemented!(),
-    };
-    Ok((key.into(), value))
+        rule => {
+            return Err(ParseError::invalid_state_with_span(
+                format!("metadata value {:?}", rule),
+                value_pair.as_span(),
+            ));
+        }
+    })
+}
+
+/// Checks that a metadata key starts with a lowercase letter, lowercasing it with a warning in
+/// lenient mode.
+fn meta_key<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<Cow<'i, str>> {
+    let key = pair.as_str();
+    if !key.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return Ok(key.into());
+    }
+    if !state.options.lenient_meta_keys {
+        return Err(ParseError::invalid_input_with_span(
+            format!("metadata key '{}' must start with a lowercase letter", key),
+            pair.as_span(),
+        ));
+    }
+    let lowercased = key[..1].to_ascii_lowercase() + &key[1..];
+    state.warnings.borrow_mut().push(ParseWarning::with_span(
+        format!("metadata key '{}' lowercased to '{}'", key, lowercased),
+        pair.as_span(),
+    ));
+    Ok(lowercased.into())
 }
 
 fn get_quoted_str<'i>(pair: Pair<'i, Rule>) -> ParseResult<Cow<'i, str>> {
@@ -773,33 +1320,53 @@ fn get_quoted_str<'i>(pair: Pair<'i, Rule>) -> ParseResult<Cow<'i, str>> {
         .into())
 }
 
-fn flag(pair: Pair<'_, Rule>) -> ParseResult<bc::Flag> {
+fn flag(pair: Pair<'_, Rule>) -> ParseResult<bc::Flag<'_>> {
     Ok(bc::Flag::from(pair.as_str()))
 }
 
+/// The per-unit number, total number and currency of a compound cost amount, with the numbers
+/// as written if the options ask for them to be kept.
+#[derive(Default)]
+struct CompoundAmount<'i> {
+    number_per: Option<Decimal>,
+    number_total: Option<Decimal>,
+    currency: Option<Cow<'i, str>>,
+    raw_per: Option<Cow<'i, str>>,
+    raw_total: Option<Cow<'i, str>>,
+}
+
 fn compound_amount<'i>(
     pair: Pair<'i, Rule>,
-) -> ParseResult<(Option<Decimal>, Option<Decimal>, Option<Cow<'i, str>>)> {
-    let mut number_per = None;
-    let mut number_total = None;
-    let mut currency = None;
+    state: &ParseState,
+) -> ParseResult<CompoundAmount<'i>> {
+    let mut amount = CompoundAmount::default();
     for p in pair.into_inner() {
         match p.as_rule() {
             Rule::num_expr => {
-                let num = Some(num_expr(p)?);
-                if number_per.is_none() {
-                    number_per = num;
+                let raw = raw_number(&p, state);
+                let span = p.as_span();
+                let num = num_expr_with_precision(p, state.options.cost_division_precision)?;
+                let num = Some(non_negative(num, "cost", span)?);
+                if amount.number_per.is_none() {
+                    amount.number_per = num;
+                    amount.raw_per = raw;
                 } else {
-                    number_total = num;
+                    amount.number_total = num;
+                    amount.raw_total = raw;
                 }
             }
             Rule::commodity => {
-                currency = Some(p.as_str().into());
+                amount.currency = Some(p.as_str().into());
+            }
+            rule => {
+                return Err(ParseError::invalid_state_with_span(
+                    format!("cost amount {:?}", rule),
+                    p.as_span(),
+                ));
             }
-            _ => unimplemented!(),
         }
     }
-    Ok((number_per, number_total, currency))
+    Ok(amount)
 }
 
 #[cfg(test)]
@@ -852,7 +1419,8 @@ mod tests {
         parse_fail!(key_value, "key    : \"value\"");
         parse_fail!(key_value, "key: bar");
         parse_fail!(key_value, "k: 123");
-        parse_fail!(key_value, "Key: 123");
+        // Uppercase keys are rejected (or lowercased) by the parser, not the grammar.
+        parse_ok!(key_value, "Key: 123");
     }
 
     #[test]
@@ -891,6 +1459,66 @@ mod tests {
         parse_ok!(num, "1,222,33.4", "1,222");
     }
 
+    #[test]
+    fn parenthesized_expressions() {
+        let ledger = parse(indoc!(
+            "
+            2001-01-01 * \"Split\"
+                Expenses:Shared  (10 + 12.50) * 3 / 4 - -1 USD
+                Assets:Cash  -((8 * 2) / (1 + 1)) USD
+            "
+        ))
+        .unwrap();
+        let postings = &ledger.transactions().next().unwrap().postings;
+        assert_eq!(postings[0].units.num, Some(Decimal::new(17875, 3)));
+        assert_eq!(postings[1].units.num, Some(Decimal::new(-8, 0)));
+    }
+
+    #[test]
+    fn scientific_notation() {
+        parse_ok!(num, "1.2E-3");
+        parse_ok!(num, "5e10");
+        parse_ok!(num, "2.5e+2");
+        parse_ok!(num, "1e", "1");
+
+        let price = |number: &str| {
+            parse(&format!("2020-01-01 price HOOL {} USD\n", number)).map(|ledger| {
+                match &ledger.directives[0] {
+                    bc::Directive::Price(price) => price.amount.num,
+                    other => panic!("expected a price, got {:?}", other),
+                }
+            })
+        };
+        assert_eq!(price("1.2E-3").unwrap(), Decimal::new(12, 4));
+        assert_eq!(price("5e10").unwrap(), Decimal::new(50_000_000_000, 0));
+        assert_eq!(price("2.5e+2").unwrap(), Decimal::new(250, 0));
+        assert!(price("1e").is_err());
+
+        let ledger = parse(indoc!(
+            "
+            2020-01-01 *
+                Assets:Trading    1e3 HOOL {1.5E-2 USD} @ 2e-2 USD
+                Assets:Cash
+            "
+        ))
+        .unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Transaction(txn) => {
+                let posting = &txn.postings[0];
+                assert_eq!(posting.units.num, Some(Decimal::new(1000, 0)));
+                assert_eq!(
+                    posting.cost.as_ref().unwrap().number_per,
+                    Some(Decimal::new(15, 3))
+                );
+                assert_eq!(
+                    posting.price.as_ref().unwrap().amount().num,
+                    Some(Decimal::new(2, 2))
+                );
+            }
+            other => panic!("expected a transaction, got {:?}", other),
+        }
+    }
+
     #[test]
     fn num_expr() {
         parse_ok!(num_expr, "1");
@@ -950,6 +1578,7 @@ mod tests {
         parse_ok!(account, "Expenses:Q1");
         parse_ok!(account, "Expenses:Tax:2018");
         parse_ok!(account, "Dash-dash:Dash-dash");
+        parse_ok!(account, "Assets:Cafe\u{301}");
 
         parse_fail!(account, "Assets");
         parse_fail!(account, "Assets:");
@@ -957,6 +1586,33 @@ mod tests {
         parse_fail!(account, "Expenses:tax");
     }
 
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn normalize_unicode_accounts() {
+        let source = "2020-01-01 open Assets:Caf\u{e9}\n2020-01-01 open Assets:Cafe\u{301}\n";
+        let accounts = |options| -> Vec<bc::Account<'_>> {
+            parse_with_options(source, &options)
+                .unwrap()
+                .directives
+                .into_iter()
+                .map(|d| match d {
+                    bc::Directive::Open(open) => open.account,
+                    other => panic!("expected an open directive, got {:?}", other),
+                })
+                .collect()
+        };
+
+        let raw = accounts(ParseOptions::default());
+        assert_ne!(raw[0], raw[1]);
+
+        let normalized = accounts(ParseOptions {
+            normalize_unicode: true,
+            ..ParseOptions::default()
+        });
+        assert_eq!(normalized[0], normalized[1]);
+        assert_eq!(normalized[0].parts, vec!["Caf\u{e9}"]);
+    }
+
     #[test]
     fn tag() {
         parse_ok!(tag, "#foo");
@@ -989,6 +1645,70 @@ mod tests {
             balance,
             "2014-08-09   balance  Assets:Cash    562.00  USD\n"
         );
+        parse_ok!(balance, "2014-08-09 balance Assets:Cash:* 562.00 USD\n");
+        parse_fail!(balance, "2014-08-09 balance Assets:Cash: * 562.00 USD\n");
+        parse_ok!(balance, "2014-08-09 balance Assets:Cash 562.00 ~ 0.5 USD\n");
+        parse_ok!(balance, "2014-08-09 balance Assets:Cash 562.00~0.5 USD\n");
+        parse_fail!(balance, "2014-08-09 balance Assets:Cash 562.00 ~ USD\n");
+        parse_fail!(balance, "2014-08-09 balance Assets:Cash 562.00 USD ~ 0.5\n");
+    }
+
+    #[test]
+    fn balance_tolerance() {
+        let ledger = parse(indoc!(
+            "
+            2014-08-09 balance Assets:Cash 562.00 USD
+              statement: \"August\"
+            2014-08-09 balance Assets:Cash 562.00 ~ 0.5 USD
+              statement: \"August\"
+            "
+        ))
+        .unwrap();
+        let balances: Vec<_> = ledger.balances().collect();
+        assert_eq!(balances.len(), 2);
+        for balance in &balances {
+            assert_eq!(balance.amount.num, Decimal::new(56200, 2));
+            assert_eq!(balance.amount.currency, "USD");
+            assert_eq!(
+                balance.meta.get("statement"),
+                Some(&bc::metadata::MetaValue::Text("August".into()))
+            );
+        }
+        assert_eq!(balances[0].tolerance, None);
+        assert_eq!(balances[1].tolerance, Some(Decimal::new(5, 1)));
+    }
+
+    #[test]
+    fn balance_directive() {
+        let ledger = parse("2014-08-09 balance Assets:Cash 562.00 USD\n").unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Balance(balance) => {
+                assert_eq!(balance.account.parts, vec!["Cash"]);
+                assert_eq!(balance.amount.num, rust_decimal::Decimal::new(56200, 2));
+                assert_eq!(balance.amount.currency, "USD");
+                assert!(!balance.inclusive);
+            }
+            other => panic!("expected a balance directive, got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "balance-wildcard")]
+    fn balance_wildcard() {
+        let ledger = parse("2014-08-09 balance Assets:Cash:* 100 USD\n").unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Balance(balance) => {
+                assert_eq!(balance.account.parts, vec!["Cash"]);
+                assert!(balance.inclusive);
+            }
+            other => panic!("expected a balance directive, got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "balance-wildcard"))]
+    fn balance_wildcard_requires_feature() {
+        assert!(parse("2014-08-09 balance Assets:Cash:* 100 USD\n").is_err());
     }
 
     #[test]
@@ -1009,6 +1729,515 @@ mod tests {
         parse_ok!(custom, "2014-07-09 custom \"budget\" \"some_config_opt_for_custom_directive\" TRUE 45.30 USD\n");
     }
 
+    #[test]
+    fn custom_with_meta() {
+        let source = indoc!(
+            "
+            2014-07-09 custom \"budget\" \"monthly\" TRUE 45.30 USD
+                owner: \"joe\"
+            "
+        );
+        let ledger = parse(source).unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Custom(custom) => {
+                assert_eq!(custom.name, "budget");
+                assert_eq!(
+                    custom.args,
+                    vec![
+                        bc::metadata::MetaValue::Text("monthly".into()),
+                        bc::metadata::MetaValue::Bool(true),
+                        bc::metadata::MetaValue::Amount(bc::Amount::new(
+                            Decimal::new(4530, 2),
+                            "USD"
+                        )),
+                    ]
+                );
+                assert_eq!(
+                    custom.meta.get("owner"),
+                    Some(&bc::metadata::MetaValue::Text("joe".into()))
+                );
+            }
+            other => panic!("expected a custom directive, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn high_precision_meta_number() {
+        let source = indoc!(
+            "
+            2014-07-09 commodity HOOL
+                rate: 1.234567890123456789012345678
+            "
+        );
+        let ledger = parse(source).unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Commodity(commodity) => match commodity.meta.get("rate") {
+                Some(bc::metadata::MetaValue::Number(num)) => {
+                    assert_eq!(num.to_string(), "1.234567890123456789012345678");
+                    assert_eq!(num.scale(), 27);
+                }
+                other => panic!("expected a number, got {:?}", other),
+            },
+            other => panic!("expected a commodity directive, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lenient_meta_keys() {
+        let source = indoc!(
+            "
+            2020-01-01 open Assets:Cash
+                Key: 1
+            2020-01-02 * \"Shop\"
+                Assets:CASH 1 USD
+                Assets:Cash:Wallet
+                    Note: \"x\"
+            "
+        );
+        assert!(parse(source).is_err());
+
+        let options = ParseOptions {
+            lenient_meta_keys: true,
+            ..ParseOptions::default()
+        };
+        let (ledger, warnings) = parse_with_warnings(source, &options).unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Open(open) => assert_eq!(
+                open.meta.get("key"),
+                Some(&bc::metadata::MetaValue::Number(1.into()))
+            ),
+            other => panic!("expected an open directive, got {:?}", other),
+        }
+        match &ledger.directives[1] {
+            bc::Directive::Transaction(txn) => {
+                assert_eq!(txn.postings.len(), 2);
+                assert!(txn.postings[1].meta.contains_key("note"));
+            }
+            other => panic!("expected a transaction, got {:?}", other),
+        }
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].location, (2, 5));
+        assert_eq!(
+            warnings[0].message,
+            "metadata key 'Key' lowercased to 'key'"
+        );
+    }
+
+    #[test]
+    fn calendar_dates() {
+        assert!(parse("2020-02-29 open Assets:Cash\n").is_ok());
+        assert!(parse("2021-02-28 open Assets:Cash\n").is_ok());
+
+        let err = parse("2021-02-29 open Assets:Cash\n").unwrap_err();
+        assert!(matches!(
+            err.kind,
+            error::ParseErrorKind::InvalidInput { .. }
+        ));
+        assert_eq!(err.location, (1, 1));
+        assert!(parse("2020-04-31 open Assets:Cash\n").is_err());
+        assert!(parse("2020-01-01 open Assets:Cash\n  since: 2021-02-29\n").is_err());
+
+        let options = ParseOptions {
+            lenient_dates: true,
+            ..ParseOptions::default()
+        };
+        let ledger = parse_with_options("2021-02-29 open Assets:Cash\n", &options).unwrap();
+        assert_eq!(
+            ledger.directives[0].date(),
+            Some(&bc::Date::from_str_unchecked("2021-02-29"))
+        );
+    }
+
+    #[test]
+    fn negative_cost_and_price() {
+        let transaction = |posting: &str| {
+            format!(
+                "2020-01-01 * \"Buy\"\n  Assets:Trading {}\n  Assets:Cash\n",
+                posting
+            )
+        };
+        assert!(parse(&transaction("10 HOOL {15 GBP} @ 20 GBP")).is_ok());
+        assert!(parse(&transaction("-10 HOOL {15 GBP} @@ 200 GBP")).is_ok());
+
+        for posting in [
+            "10 HOOL {-15 GBP}",
+            "10 HOOL {{-150 GBP}}",
+            "10 HOOL {15 # -5 GBP}",
+            "10 HOOL @ -20 GBP",
+            "10 HOOL @@ -200 GBP",
+        ] {
+            let err = parse(&transaction(posting)).unwrap_err();
+            assert!(
+                matches!(err.kind, error::ParseErrorKind::InvalidInput { .. }),
+                "{}: {:?}",
+                posting,
+                err
+            );
+            assert_eq!(err.location.0, 2, "{}", posting);
+        }
+    }
+
+    #[test]
+    fn price_spec_variants() {
+        let source = indoc!(
+            "
+            2012-11-03 * \"Transfer to account in Canada\"
+                Assets:MyBank:Checking            -400.00 USD @ 1.09 CAD
+                Assets:MyBank:Checking            -400.00 USD @@ 436.01 CAD
+                Assets:FR:SocGen:Checking          872.02 CAD
+            "
+        );
+        let ledger = parse(source).unwrap();
+        let postings = &ledger.transactions().next().unwrap().postings;
+        let cad = |num, scale| Some(bc::Amount::new(Decimal::new(num, scale), "CAD").into());
+        assert_eq!(postings[0].price, cad(109, 2).map(bc::PriceSpec::PerUnit));
+        assert_eq!(postings[1].price, cad(43601, 2).map(bc::PriceSpec::Total));
+        assert_eq!(postings[2].price, None);
+    }
+
+    #[test]
+    fn book_parsed_buy_and_sale() {
+        let source = indoc!(
+            "
+            2020-01-01 * \"Buy\"
+                Assets:Broker     10 HOOL {500 USD}
+                Assets:Cash    -5000 USD
+            2020-03-01 * \"Sell\"
+                Assets:Broker     -4 HOOL {} @ 550 USD
+                Assets:Cash     2200 USD
+                Income:Gains
+            "
+        );
+        let ledger = parse(source).unwrap();
+        let mut inventory = bc::Inventory::new();
+        let mut gains = Vec::new();
+        for txn in ledger.transactions() {
+            for posting in txn
+                .postings
+                .iter()
+                .filter(|p| p.account.parts == ["Broker"])
+            {
+                gains.push(
+                    inventory
+                        .book_posting(posting, &txn.date, &bc::Booking::Fifo)
+                        .unwrap(),
+                );
+            }
+        }
+        assert_eq!(
+            gains,
+            vec![vec![], vec![bc::Amount::new(200.into(), "USD")]]
+        );
+        let lot = &inventory.positions()[0];
+        assert_eq!(inventory.positions().len(), 1);
+        assert_eq!(lot.units, bc::Amount::new(6.into(), "HOOL"));
+        let cost = lot.cost.as_ref().unwrap();
+        assert_eq!((cost.number, cost.currency.as_ref()), (500.into(), "USD"));
+        assert_eq!(cost.date, bc::Date::from_str_unchecked("2020-01-01"));
+    }
+
+    #[test]
+    fn odd_input_is_an_error_not_a_panic() {
+        let inputs = [
+            "2020-01-01 * \"Buy\"\n  Assets:Trading 10 HOOL {1 / 0 USD}\n  Assets:Cash\n",
+            "2020-01-01 * \"Buy\"\n  Assets:Trading 1 / 0 HOOL\n  Assets:Cash\n",
+            "2020-01-01 * \"Buy\"\n  Assets:Trading 79228162514264337593543950335 * 2 HOOL\n",
+            "2020-01-01 * \"Buy\"\n  Assets:Trading 10 HOOL @ 1 / 0 USD\n  Assets:Cash\n",
+            "2020-01-01 open Assets:Cash\n  ratio: 1 / 0\n",
+        ];
+        for input in inputs.iter() {
+            let result = std::panic::catch_unwind(|| parse(input).map(|_| ()));
+            let err = result
+                .unwrap_or_else(|_| panic!("parsing panicked on {:?}", input))
+                .expect_err(input);
+            assert!(
+                matches!(err.kind, error::ParseErrorKind::InvalidInput { .. }),
+                "{:?}: {:?}",
+                input,
+                err
+            );
+        }
+
+        // A line of tags in the body of a transaction applies to the transaction.
+        let ledger =
+            parse("2020-01-01 * \"Buy\"\n  #trip\n  Assets:Cash 1 USD\n  Assets:Bank\n").unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Transaction(txn) => {
+                assert!(txn.tags.contains("trip"));
+                assert_eq!(txn.postings.len(), 2);
+            }
+            other => panic!("expected a transaction, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn per_unit_in_total_cost() {
+        let source =
+            "2020-01-01 * \"Buy\"\n  Assets:Trading 10 HOOL {{15 # 5 GBP}}\n  Assets:Cash\n";
+        let result = std::panic::catch_unwind(|| parse(source).map(|_| ()));
+        let err = result.expect("parsing panicked").unwrap_err();
+        assert!(matches!(
+            err.kind,
+            error::ParseErrorKind::InvalidInput { .. }
+        ));
+        assert_eq!(err.location, (2, 26));
+    }
+
+    #[test]
+    fn cost_division_precision() {
+        let source = indoc!(
+            "
+            2020-01-01 * \"Buy\"
+                Assets:Trading 3 HOOL { 1 / 3 USD } @ 1 / 3 USD
+                Assets:Cash
+            "
+        );
+        let number_per = |ledger: &bc::Ledger<'_>| match &ledger.directives[0] {
+            bc::Directive::Transaction(txn) => txn.postings[0]
+                .cost
+                .as_ref()
+                .and_then(|cost| cost.number_per)
+                .unwrap(),
+            other => panic!("expected a transaction, got {:?}", other),
+        };
+
+        let ledger = parse(source).unwrap();
+        assert_eq!(number_per(&ledger).scale(), 28);
+
+        let options = ParseOptions {
+            cost_division_precision: Some(12),
+            ..ParseOptions::default()
+        };
+        let ledger = parse_with_options(source, &options).unwrap();
+        assert_eq!(number_per(&ledger), Decimal::new(333_333_333_333, 12));
+        assert_eq!(number_per(&ledger).scale(), 12);
+        // Only cost numbers are affected.
+        match &ledger.directives[0] {
+            bc::Directive::Transaction(txn) => match &txn.postings[0].price {
+                Some(bc::PriceSpec::PerUnit(price)) => {
+                    assert_eq!(price.num.unwrap().scale(), 28)
+                }
+                other => panic!("expected a per-unit price, got {:?}", other),
+            },
+            other => panic!("expected a transaction, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn directive_locations() {
+        let source = indoc!(
+            "
+            option \"title\" \"Books\"
+
+            2020-01-01 open Assets:Cash
+            pushtag #trip
+            2020-01-02 * \"Coffee\"
+                Expenses:Food    3.00 USD
+                Assets:Cash
+            poptag #trip
+            ; comment
+            2020-01-03 close Assets:Cash
+            "
+        );
+        let (ledger, locations) = parse_with_locations(source, &ParseOptions::default()).unwrap();
+        assert_eq!(ledger.directives.len(), locations.len());
+        let lines: Vec<_> = locations.iter().map(|location| location.line).collect();
+        assert_eq!(lines, vec![1, 3, 5, 10]);
+        assert!(locations.iter().all(|location| location.column == 1));
+    }
+
+    /// Writes `files` into a fresh directory under the system temp dir.
+    fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("beancount-parser-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (file, contents) in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parse_files_parallel_keeps_order() {
+        let files: Vec<_> = (0..8)
+            .map(|i| {
+                (
+                    format!("{}.beancount", i),
+                    format!("2020-01-{:02} open Assets:Cash{}\n", i + 1, i),
+                )
+            })
+            .collect();
+        let files: Vec<_> = files
+            .iter()
+            .map(|(name, contents)| (name.as_str(), contents.as_str()))
+            .chain([("bad.beancount", "2020-01-01 open\n")])
+            .collect();
+        let dir = write_files("parallel", &files);
+        let mut paths: Vec<_> = (0..8)
+            .map(|i| dir.join(format!("{}.beancount", i)))
+            .collect();
+        paths.insert(3, dir.join("bad.beancount"));
+        paths.push(dir.join("missing.beancount"));
+
+        let results = parse_files_parallel(&paths);
+        assert_eq!(results.len(), 10);
+        let mut i = 0;
+        for (path, result) in paths.iter().zip(results) {
+            if path.ends_with("bad.beancount") || path.ends_with("missing.beancount") {
+                assert_eq!(result.unwrap_err().file.as_ref(), Some(path));
+                continue;
+            }
+            let ledger = result.unwrap();
+            match &ledger.directives[..] {
+                [bc::Directive::Open(open)] => {
+                    assert_eq!(open.account.parts, vec![format!("Cash{}", i)]);
+                }
+                other => panic!("expected an open directive, got {:?}", other),
+            }
+            i += 1;
+        }
+        assert_eq!(i, 8);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn parse_reader_returns_owned_ledger() {
+        let source = "2014-05-01 open Assets:Cash\n2014-05-02 close Assets:Cash\n".to_string();
+        let ledger = parse_reader(std::io::Cursor::new(source.clone().into_bytes())).unwrap();
+        drop(source);
+        assert_eq!(ledger.directives.len(), 2);
+        assert_eq!(ledger.directives[1].kind(), "close");
+
+        let err = parse_reader(&b"2014-05-01 open Assets:\xff\n"[..]).unwrap_err();
+        assert!(matches!(err.kind, error::ParseErrorKind::Io { .. }));
+        assert!(parse_reader(&b"2014-05-01 open\n"[..]).is_err());
+    }
+
+    #[test]
+    fn parse_file_recursive_splices_includes() {
+        let dir = write_files(
+            "include",
+            &[
+                (
+                    "main.beancount",
+                    "2020-01-01 open Assets:Cash\ninclude \"sub/accounts.beancount\"\n2020-01-03 close Assets:Cash\n",
+                ),
+                (
+                    "sub/accounts.beancount",
+                    "2020-01-02 open Assets:Bank\n",
+                ),
+            ],
+        );
+        let ledger = parse_file_recursive(&dir.join("main.beancount")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let dates: Vec<_> = ledger
+            .directives
+            .iter()
+            .map(|directive| directive.date().unwrap().to_string())
+            .collect();
+        assert_eq!(dates, vec!["2020-01-01", "2020-01-02", "2020-01-03"]);
+        assert!(!ledger
+            .directives
+            .iter()
+            .any(|directive| matches!(directive, bc::Directive::Include(_))));
+    }
+
+    #[test]
+    fn parse_file_recursive_include_cycle() {
+        let dir = write_files(
+            "cycle",
+            &[
+                ("a.beancount", "include \"b.beancount\"\n"),
+                (
+                    "b.beancount",
+                    "2020-01-01 open Assets:Cash\ninclude \"a.beancount\"\n",
+                ),
+            ],
+        );
+        let err = parse_file_recursive(&dir.join("a.beancount")).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(
+            err.kind,
+            error::ParseErrorKind::InvalidInput { .. }
+        ));
+        assert_eq!(err.file, Some(dir.join("b.beancount")));
+        assert_eq!(err.location, (2, 1));
+        assert!(err.to_string().contains("a.beancount"), "{}", err);
+    }
+
+    #[test]
+    fn parse_iter_is_lazy() {
+        let source = indoc!(
+            "
+            pushtag #trip
+            2020-01-01 * \"Coffee\"
+                Expenses:Food    3.00 USD
+                Assets:Cash
+            2020-01-02 open Assets:Bank
+            poptag #trip
+            2021-02-29 open Assets:Invalid
+            "
+        );
+        let directives: Vec<_> = parse_iter(source)
+            .take(2)
+            .collect::<ParseResult<_>>()
+            .unwrap();
+        assert_eq!(directives.len(), 2);
+        match &directives[0] {
+            bc::Directive::Transaction(txn) => assert!(txn.tags.contains("trip")),
+            other => panic!("expected a transaction, got {:?}", other),
+        }
+
+        // The third directive is only converted, and rejected, when iterating further.
+        let results: Vec<_> = parse_iter(source).collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_err());
+
+        let results: Vec<_> = parse_iter("2020-01-01 open\n").collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+
+    #[test]
+    fn into_owned_outlives_input() {
+        let ledger = {
+            let source = String::from(indoc!(
+                "
+                2020-01-01 open Assets:Cash USD
+                  note: \"wallet\"
+                2020-01-02 * \"Cafe\" \"Coffee\" #trip
+                  Expenses:Food  3.00 USD
+                  Assets:Cash
+                "
+            ));
+            let ledger = parse(&source).unwrap();
+            assert!(ledger.directives.iter().any(|d| d.source().is_some()));
+            ledger.into_owned()
+        };
+
+        match &ledger.directives[1] {
+            bc::Directive::Transaction(txn) => {
+                assert_eq!(txn.payee.as_deref(), Some("Cafe"));
+                assert!(txn.tags.contains("trip"));
+                assert_eq!(txn.postings[0].units.currency.as_deref(), Some("USD"));
+                assert_eq!(txn.source, None);
+            }
+            other => panic!("expected a transaction, got {:?}", other),
+        }
+        match &ledger.directives[0] {
+            bc::Directive::Open(open) => assert_eq!(
+                open.meta.get("note"),
+                Some(&bc::metadata::MetaValue::Text("wallet".into()))
+            ),
+            other => panic!("expected an open, got {:?}", other),
+        }
+    }
+
     #[test]
     fn document() {
         parse_ok!(
@@ -1088,7 +2317,7 @@ mod tests {
             "
         );
         assert_eq!(
-            parse(&source).unwrap(),
+            parse(source).unwrap(),
             bc::Ledger {
                 directives: vec![
                     bc::Directive::Plugin(
@@ -1112,6 +2341,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn blank_lines_before() {
+        let source = indoc!(
+            "
+            option \"title\" \"Ledger\"
+
+            ; A comment line is not blank.
+            2014-07-09 price HOOL 579.18 USD
+
+
+            2016-11-28 close Liabilities:CreditCard:CapitalOne
+            "
+        );
+        let blank_lines: Vec<u8> = parse(source)
+            .unwrap()
+            .directives
+            .iter()
+            .map(bc::Directive::blank_lines_before)
+            .collect();
+        assert_eq!(blank_lines, vec![0, 1, 2]);
+    }
+
     #[test]
     fn price() {
         parse_ok!(price, "2014-07-09 price HOOL 579.18 USD\n");
@@ -1137,6 +2388,100 @@ mod tests {
         parse_ok!(posting, "Assets:Cash 200 XYZ {{ 200 USD }}");
         parse_ok!(posting, "Assets:Cash 200 XYZ {}");
         parse_ok!(posting, "Assets:Cash 200 XYZ {{}}");
+        parse_ok!(posting, "Assets:Cash\t200\tUSD");
+        parse_ok!(posting, "Assets:Cash\t\t200 XYZ\t{\t200\tUSD }\t@\t1\tUSD");
+    }
+
+    #[test]
+    fn metadata_attachment_by_indentation() {
+        let source = indoc!(
+            "
+            2020-01-01 * \"Groceries\"
+              receipt: \"1234\"
+              Expenses:Food  10 USD
+              Assets:Cash  -10 USD
+                category: \"cash\"
+              reviewed: TRUE
+            \tExpenses:Food  1 USD
+            \t    note: \"tip\"
+            \tAssets:Cash  -1 USD
+            \t        note: \"change\"
+            "
+        );
+        let ledger = parse(source).unwrap();
+        let txn = match &ledger.directives[0] {
+            bc::Directive::Transaction(txn) => txn,
+            other => panic!("expected a transaction, got {:?}", other),
+        };
+        let keys = |meta: &bc::metadata::Meta<'_>| {
+            let mut keys: Vec<_> = meta.keys().map(|k| k.to_string()).collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(keys(&txn.meta), vec!["receipt", "reviewed"]);
+        assert!(txn.postings[0].meta.is_empty());
+        assert_eq!(keys(&txn.postings[1].meta), vec!["category"]);
+        assert_eq!(keys(&txn.postings[2].meta), vec!["note"]);
+        assert_eq!(
+            txn.postings[3].meta.get("note"),
+            Some(&bc::metadata::MetaValue::Text("change".into()))
+        );
+    }
+
+    #[test]
+    fn indent_width() {
+        assert_eq!(super::indent_width("  "), 2);
+        assert_eq!(super::indent_width("\t"), 8);
+        assert_eq!(super::indent_width("  \t "), 9);
+    }
+
+    #[test]
+    fn transaction_without_strings() {
+        let ledger = parse("2020-01-01 *\n  Assets:X 1 USD\n  Assets:Y\n").unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Transaction(txn) => {
+                assert_eq!(txn.flag, bc::Flag::Okay);
+                assert_eq!(txn.payee, None);
+                assert_eq!(txn.narration, "");
+                assert_eq!(txn.postings.len(), 2);
+            }
+            other => panic!("expected a transaction, got {:?}", other),
+        }
+        parse_ok!(transaction, "2020-01-01 ! #tag\n  Assets:X 1 USD\n");
+    }
+
+    #[test]
+    fn minimal_whitespace_transaction() {
+        let ledger = parse("2019-02-19 txn\"Foo\"\"Bar\"#tag\n  Assets:Cash 1 USD\n").unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Transaction(txn) => {
+                assert_eq!(txn.flag, bc::Flag::Okay);
+                assert_eq!(txn.payee, Some("Foo".into()));
+                assert_eq!(txn.narration, "Bar");
+                assert!(txn.tags.contains("tag"));
+                assert_eq!(txn.postings.len(), 1);
+            }
+            other => panic!("expected a transaction, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tab_separated_amounts() {
+        let ledger =
+            parse("2020-01-01 * \"Paycheck\"\n\tAssets:Cash\t200\tUSD\n\tIncome:Salary\n").unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Transaction(txn) => {
+                assert_eq!(txn.postings.len(), 2);
+                assert_eq!(
+                    txn.postings[0].units,
+                    bc::IncompleteAmount::builder()
+                        .num(Some(200.into()))
+                        .currency(Some("USD".into()))
+                        .build()
+                );
+            }
+            other => panic!("expected a transaction, got {:?}", other),
+        }
     }
 
     #[test]
@@ -1188,15 +2533,37 @@ mod tests {
         assert_eq!(None, state.pushed_tags.get("sometag"));
     }
 
-    fn get_sorted_tags<'a>(state: &'a ParseState) -> Vec<&'a str> {
-        let mut tags = state
-            .get_pushed_tags()
-            .map(|a| *a)
-            .collect::<Vec<&'a str>>();
+    fn get_sorted_tags<'a>(state: &ParseState<'a>) -> Vec<&'a str> {
+        let mut tags = state.get_pushed_tags().collect::<Vec<&'a str>>();
         tags.sort();
         tags
     }
 
+    #[test]
+    fn pushed_tags_borrow_input() {
+        let mut source = String::from("pushtag #trip\n");
+        for i in 0..200 {
+            source.push_str(&format!(
+                "2020-01-01 * \"Cafe {}\" #food\n  Expenses:Food 1 USD\n  Assets:Cash\n",
+                i
+            ));
+        }
+        source.push_str(
+            "poptag #trip\n2020-01-02 * \"Home\"\n  Expenses:Food 1 USD\n  Assets:Cash\n",
+        );
+
+        let ledger = parse(&source).unwrap();
+        let transactions: Vec<_> = ledger.transactions().collect();
+        assert_eq!(transactions.len(), 201);
+        for txn in &transactions[..200] {
+            assert_eq!(txn.tags.len(), 2);
+            let tag = txn.tags.get("trip").unwrap();
+            assert!(matches!(tag, Cow::Borrowed(_)));
+            assert_eq!(*tag, Cow::<str>::Owned("trip".to_string()));
+        }
+        assert!(transactions[200].tags.is_empty());
+    }
+
     #[test]
     fn test_iter() {
         let mut state = ParseState::new();
@@ -1224,14 +2591,14 @@ mod tests {
             pushtag #social
             "
         );
-        assert!(parse(&source).is_err());
+        assert!(parse(source).is_err());
 
         let source = indoc!(
             "
             poptag #social
             "
         );
-        assert!(parse(&source).is_err());
+        assert!(parse(source).is_err());
 
         let source = indoc!(
             "
@@ -1239,7 +2606,7 @@ mod tests {
             poptag #social
             "
         );
-        assert!(parse(&source).is_ok());
+        assert!(parse(source).is_ok());
 
         let source = indoc!(
             "
@@ -1254,7 +2621,7 @@ mod tests {
             poptag #social
             "
         );
-        assert!(parse(&source).is_ok());
+        assert!(parse(source).is_ok());
         let source = indoc!(
             "
             pushtag #rust-is-cool
@@ -1262,7 +2629,7 @@ mod tests {
             poptag #social
             "
         );
-        assert!(parse(&source).is_err());
+        assert!(parse(source).is_err());
     }
 
     #[test]
@@ -1330,7 +2697,7 @@ mod tests {
                             )))
                             .build()])
                         .tags(
-                            vec!["social", "alcohol"]
+                            ["social", "alcohol"]
                                 .iter()
                                 .map(|a| Cow::from(*a))
                                 .collect::<HashSet<Tag<'_>>>()
@@ -1342,6 +2709,53 @@ mod tests {
         )
     }
 
+    #[test]
+    fn txn_flag() {
+        for flag in [
+            "*", "txn", "!", "P", "S", "T", "C", "U", "R", "M", "#", "&", "?", "%",
+        ] {
+            parse_ok!(txn_flag, flag);
+        }
+        parse_fail!(txn_flag, "@");
+        parse_fail!(txn_flag, "x");
+    }
+
+    #[test]
+    fn typed_flags() {
+        let source = indoc!(
+            "
+            2020-01-01 P \"Narration\"
+                M Assets:Cash 1 USD
+            "
+        );
+        let ledger = parse(source).unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Transaction(txn) => {
+                assert_eq!(txn.flag, bc::Flag::Padding);
+                assert_eq!(txn.postings[0].flag, Some(bc::Flag::Merging));
+            }
+            other => panic!("expected a transaction, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_flag_is_other() {
+        let source = indoc!(
+            "
+            2020-01-01 & \"Narration\"
+                ? Assets:Cash 1 USD
+            "
+        );
+        let ledger = parse(source).unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Transaction(txn) => {
+                assert_eq!(txn.flag, bc::Flag::Other("&".into()));
+                assert_eq!(txn.postings[0].flag, Some(bc::Flag::Other("?".into())));
+            }
+            other => panic!("expected a transaction, got {:?}", other),
+        }
+    }
+
     #[test]
     fn transaction() {
         parse_ok!(
@@ -1355,6 +2769,10 @@ mod tests {
             )
         );
         parse_ok!(transaction, "2019-02-19*\"Foo\"\"Bar\"\n");
+        parse_ok!(transaction, "2019-02-19 txn\"Foo\"\n");
+        parse_ok!(transaction, "2019-02-19txn\"Foo\"\"Bar\"\n");
+        parse_ok!(transaction, "2019-02-19!\"Foo\"|\"Bar\"\n");
+        parse_ok!(transaction, "2019-02-19*\"Foo\"\"Bar\"#tag^link\n");
         parse_ok!(
             transaction,
             indoc!(
@@ -1414,7 +2832,7 @@ mod tests {
             "
         );
         assert_eq!(
-            parse(&source).unwrap(),
+            parse(source).unwrap(),
             bc::Ledger {
                 directives: vec![bc::Directive::Transaction(
                     bc::Transaction::builder()
@@ -1422,13 +2840,13 @@ mod tests {
                         .payee(Some("Cafe Mogador".into()))
                         .narration("Lamb tagine with wine".into())
                         .tags(
-                            vec!["tag"]
+                            ["tag"]
                                 .iter()
                                 .map(|a| Cow::from(*a))
                                 .collect::<HashSet<Tag<'_>>>()
                         )
                         .links(
-                            vec!["link"]
+                            ["link"]
                                 .iter()
                                 .map(|a| Cow::from(*a))
                                 .collect::<HashSet<Tag<'_>>>()
@@ -1473,7 +2891,7 @@ mod tests {
             "
         );
         assert_eq!(
-            parse(&source).unwrap(),
+            parse(source).unwrap(),
             bc::Ledger {
                 directives: vec![bc::Directive::Transaction(
                     bc::Transaction::builder()
@@ -1481,13 +2899,13 @@ mod tests {
                         .payee(Some("Cafe Mogador".into()))
                         .narration("Lamb tagine with wine".into())
                         .tags(
-                            vec!["tag"]
+                            ["tag"]
                                 .iter()
                                 .map(|a| Cow::from(*a))
                                 .collect::<HashSet<Tag<'_>>>()
                         )
                         .links(
-                            vec!["link"]
+                            ["link"]
                                 .iter()
                                 .map(|a| Cow::from(*a))
                                 .collect::<HashSet<Tag<'_>>>()
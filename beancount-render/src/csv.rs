@@ -0,0 +1,68 @@
+use beancount_core::Ledger;
+use std::io::Write;
+
+use crate::{BasicRenderer, BasicRendererError, Renderer};
+
+/// Writes every posting of the ledger as a row of comma-separated values, preceded by a header
+/// row. Costs and prices are written in their beancount form, e.g. `{500.00 USD}` and
+/// `@ 585.00 USD`.
+pub fn write_csv<W: Write>(w: &mut W, ledger: &Ledger<'_>) -> Result<(), BasicRendererError> {
+    let renderer = BasicRenderer::default();
+    writeln!(
+        w,
+        "date,flag,payee,narration,account,amount,currency,cost,price"
+    )?;
+    for (txn, posting) in ledger.postings() {
+        let mut account = Vec::new();
+        renderer.render(&posting.account, &mut account)?;
+        let mut cost = Vec::new();
+        if let Some(cost_spec) = &posting.cost {
+            renderer.render(cost_spec, &mut cost)?;
+        }
+        let mut price = Vec::new();
+        if let Some(price_spec) = &posting.price {
+            renderer.render(price_spec, &mut price)?;
+        }
+        let fields = [
+            txn.date.to_string(),
+            txn.flag.to_string(),
+            unescape(txn.payee.as_deref().unwrap_or_default()),
+            unescape(&txn.narration),
+            String::from_utf8_lossy(&account).into_owned(),
+            posting.units.num.map(|n| n.to_string()).unwrap_or_default(),
+            posting
+                .units
+                .currency
+                .as_deref()
+                .unwrap_or_default()
+                .to_string(),
+            String::from_utf8_lossy(&cost).into_owned(),
+            String::from_utf8_lossy(&price).into_owned(),
+        ];
+        let row: Vec<_> = fields.iter().map(|field| escape(field)).collect();
+        writeln!(w, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+/// Resolves the backslash escapes of a beancount string, which the parser keeps verbatim.
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Quotes a field if it contains a delimiter, quote or line break, doubling embedded quotes.
+fn escape(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -1,32 +1,221 @@
 use beancount_core::*;
-use metadata::MetaValue;
+use metadata::{Link, MetaKeyError, MetaValue, Tag};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::{io, io::Write};
 use thiserror::Error;
 
+pub use csv::write_csv;
+
+mod csv;
 #[cfg(test)]
 mod tests;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
-pub struct BasicRenderer {}
+/// Layout of the lines rendered by a [`BasicRenderer`](struct.BasicRenderer.html).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct RenderOptions {
+    /// One level of indentation, written before postings and directive metadata, and twice
+    /// before posting metadata. Defaults to a tab; beancount's own style is two spaces.
+    pub indent: &'static str,
+
+    /// The column, counted in characters from the start of the line, at which posting amounts
+    /// start. Postings whose flag and account reach past it are followed by two spaces instead.
+    /// When `None`, the amount is separated from the account by a tab.
+    pub amount_column: Option<usize>,
+
+    /// Pads the flag and account of every posting in a transaction to the widest one, so that
+    /// the amounts of the transaction line up in a column.
+    pub align_transaction: bool,
+
+    /// Writes the integer part of numbers with a `,` between each group of three digits
+    /// (`1,234,567.89`), like beancount's `render_commas` option. Numbers kept as written by
+    /// the parser are rendered unchanged.
+    pub render_commas: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            indent: "\t",
+            amount_column: None,
+            align_transaction: false,
+            render_commas: false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BasicRenderer {
+    account_separator: char,
+    sort_postings: bool,
+    validate_meta_keys: bool,
+    skip_unsupported: bool,
+    options: RenderOptions,
+}
+
+impl Default for BasicRenderer {
+    fn default() -> Self {
+        Self {
+            account_separator: ':',
+            sort_postings: false,
+            validate_meta_keys: false,
+            skip_unsupported: false,
+            options: RenderOptions::default(),
+        }
+    }
+}
 
 impl BasicRenderer {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Sets the separator written between the components of an account name, e.g. `/` to
+    /// render `Assets/US/Cash` for tools that don't use beancount's `:`.
+    ///
+    /// Output rendered with any separator other than `:` is not valid beancount and can't be
+    /// parsed back; see [`round_trips`](#method.round_trips).
+    pub fn with_account_separator(mut self, separator: char) -> Self {
+        self.account_separator = separator;
+        self
+    }
+
+    /// When set, the postings of a transaction are rendered sorted by account name instead of in
+    /// input order. A posting with an elided amount is kept last.
+    pub fn with_sorted_postings(mut self, sort_postings: bool) -> Self {
+        self.sort_postings = sort_postings;
+        self
+    }
+
+    /// When set, metadata keys are checked with
+    /// [`validate_meta_key`](../beancount_core/metadata/fn.validate_meta_key.html) before being
+    /// written, and rendering fails on a key that couldn't be parsed back.
+    pub fn with_meta_key_validation(mut self, validate_meta_keys: bool) -> Self {
+        self.validate_meta_keys = validate_meta_keys;
+        self
+    }
+
+    /// Unsupported directives are written as their text. When set, one without any text is
+    /// rendered as a `; unsupported directive` comment instead of failing with
+    /// [`BasicRendererError::Unsupported`](enum.BasicRendererError.html#variant.Unsupported), so
+    /// that the supported parts of a ledger can still be rendered.
+    pub fn with_skip_unsupported(mut self, skip_unsupported: bool) -> Self {
+        self.skip_unsupported = skip_unsupported;
+        self
+    }
+
+    /// When set, the amounts of a transaction's postings are rendered in a single column, after
+    /// the widest posting flag and account of the transaction. This is a shorthand for
+    /// [`RenderOptions::align_transaction`](struct.RenderOptions.html#structfield.align_transaction).
+    pub fn with_aligned_amounts(mut self, align_amounts: bool) -> Self {
+        self.options.align_transaction = align_amounts;
+        self
+    }
+
+    /// Sets the indentation and alignment of rendered lines.
+    pub fn with_options(mut self, options: RenderOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Writes the directive's original source text if it has one, and renders it otherwise.
+    ///
+    /// This preserves parsed directives byte for byte, including comments and formatting, while
+    /// still rendering directives that were created or had their source cleared.
+    pub fn render_or_source<W: Write>(
+        &self,
+        directive: &Directive<'_>,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError> {
+        match directive.source() {
+            Some(source) => Ok(w.write_all(source.as_bytes())?),
+            None => self.render(directive, w),
+        }
+    }
+
+    /// Renders the ledger into a `String`.
+    pub fn render_to_string(&self, ledger: &Ledger<'_>) -> Result<String, BasicRendererError> {
+        let mut rendered = Vec::new();
+        self.render(ledger, &mut rendered)?;
+        Ok(String::from_utf8(rendered)?)
+    }
+
+    /// Whether the output of this renderer can be parsed back into the same ledger.
+    pub fn round_trips(&self) -> bool {
+        self.account_separator == ':'
+    }
 }
 
 pub fn render<W: Write>(w: &mut W, ledger: &Ledger<'_>) -> Result<(), BasicRendererError> {
     BasicRenderer::default().render(ledger, w)
 }
 
+/// Renders the ledger into a `String` with the default [`BasicRenderer`](struct.BasicRenderer.html).
+pub fn render_to_string(ledger: &Ledger<'_>) -> Result<String, BasicRendererError> {
+    BasicRenderer::default().render_to_string(ledger)
+}
+
+/// Wraps the ledger so that it can be formatted with `{}`, as rendered by the default
+/// [`BasicRenderer`](struct.BasicRenderer.html).
+///
+/// # Example
+/// ```rust
+/// let ledger = beancount_parser::parse("2014-05-01 open Assets:Cash\n").unwrap();
+/// assert_eq!(
+///     beancount_render::display(&ledger).to_string(),
+///     "2014-05-01 open Assets:Cash\n"
+/// );
+/// ```
+pub fn display<'l, 'a>(ledger: &'l Ledger<'a>) -> LedgerDisplay<'l, 'a> {
+    LedgerDisplay(ledger)
+}
+
+/// A ledger formatted by the default [`BasicRenderer`](struct.BasicRenderer.html), returned by
+/// [`display`](fn.display.html).
+///
+/// Formatting fails with `fmt::Error` if the ledger can't be rendered, e.g. because it contains
+/// an unsupported directive; use [`render_to_string`](fn.render_to_string.html) to get the
+/// underlying error.
+#[derive(Copy, Clone, Debug)]
+pub struct LedgerDisplay<'l, 'a>(pub &'l Ledger<'a>);
+
+impl fmt::Display for LedgerDisplay<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rendered = render_to_string(self.0).map_err(|_| fmt::Error)?;
+        f.write_str(&rendered)
+    }
+}
+
+/// Writes the directive's original source text if it has one, and renders it with the default
+/// [`BasicRenderer`](struct.BasicRenderer.html) otherwise.
+pub fn render_or_source<W: Write>(
+    w: &mut W,
+    directive: &Directive<'_>,
+) -> Result<(), BasicRendererError> {
+    BasicRenderer::default().render_or_source(directive, w)
+}
+
 #[derive(Error, Debug)]
 pub enum BasicRendererError {
     #[error("an io error occurred")]
     Io(#[from] io::Error),
-    #[error("could not render unsupported directive")]
+    #[error("rendered output is not valid UTF-8")]
+    Utf8(#[from] std::string::FromUtf8Error),
+    #[error("could not render unsupported directive without text")]
     Unsupported,
+    #[error(transparent)]
+    InvalidMetaKey(#[from] MetaKeyError),
+    /// Rendering the directive at `index` of a ledger failed.
+    #[error("could not render {kind} directive {index} of the ledger")]
+    Directive {
+        /// Position of the directive in the ledger.
+        index: usize,
+        /// The kind of directive, as given by `Directive::kind`.
+        kind: &'static str,
+        #[source]
+        source: Box<BasicRendererError>,
+    },
 }
 
 pub trait Renderer<T, W: Write> {
@@ -37,9 +226,16 @@ pub trait Renderer<T, W: Write> {
 impl<'a, W: Write> Renderer<&'a Ledger<'_>, W> for BasicRenderer {
     type Error = BasicRendererError;
     fn render(&self, ledger: &'a Ledger<'_>, write: &mut W) -> Result<(), Self::Error> {
-        for directive in &ledger.directives {
-            self.render(directive, write)?;
-            writeln!(write)?;
+        for (index, directive) in ledger.directives.iter().enumerate() {
+            for _ in 0..directive.blank_lines_before() {
+                writeln!(write)?;
+            }
+            self.render(directive, write)
+                .map_err(|source| BasicRendererError::Directive {
+                    index,
+                    kind: directive.kind(),
+                    source: Box::new(source),
+                })?;
         }
         Ok(())
     }
@@ -48,11 +244,12 @@ impl<'a, W: Write> Renderer<&'a Ledger<'_>, W> for BasicRenderer {
 impl<'a, W: Write> Renderer<&'a Document<'_>, W> for BasicRenderer {
     type Error = BasicRendererError;
     fn render(&self, document: &'a Document<'_>, write: &mut W) -> Result<(), Self::Error> {
-        // TODO: Tags? Links?
         write!(write, "{} document ", document.date)?;
         self.render(&document.account, write)?;
-        writeln!(write, " \"{}\"", document.path)?;
-        render_key_value(self, write, &document.meta)?;
+        write!(write, " \"{}\"", document.path)?;
+        render_tags_links(write, &document.tags, &document.links)?;
+        writeln!(write)?;
+        render_key_value(self, write, &document.meta, 1)?;
         Ok(())
     }
 }
@@ -77,18 +274,88 @@ impl<'a, W: Write> Renderer<&'a Directive<'_>, W> for BasicRenderer {
             Price(price) => self.render(price, write),
             Query(query) => self.render(query, write),
             Transaction(transaction) => self.render(transaction, write),
-            Unsupported => Err(BasicRendererError::Unsupported),
+            Unsupported(text) if !text.is_empty() => {
+                write!(write, "{}", text)?;
+                if !text.ends_with('\n') {
+                    writeln!(write)?;
+                }
+                Ok(())
+            }
+            Unsupported(_) if self.skip_unsupported => {
+                Ok(writeln!(write, "; unsupported directive")?)
+            }
+            Unsupported(_) => Err(BasicRendererError::Unsupported),
+        }
+    }
+}
+
+impl BasicRenderer {
+    /// The number as written in the input, if it was kept by the parser, and `num` formatted
+    /// according to the options otherwise.
+    fn number(&self, num: &impl fmt::Display, raw: &Option<Cow<'_, str>>) -> String {
+        match raw {
+            Some(raw) => raw.to_string(),
+            None if self.options.render_commas => group_thousands(&num.to_string()),
+            None => num.to_string(),
         }
     }
 }
 
+/// Inserts a `,` between each group of three digits of the integer part of a formatted number,
+/// leaving the sign and the fractional part alone.
+fn group_thousands(num: &str) -> String {
+    let (sign, unsigned) = match num.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", num),
+    };
+    let (integer, fraction) = unsigned.split_at(unsigned.find('.').unwrap_or(unsigned.len()));
+    let mut grouped = String::with_capacity(num.len() + integer.len() / 3);
+    grouped.push_str(sign);
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(fraction);
+    grouped
+}
+
+/// Writes tags and links, each preceded by a space, in sorted order so that output is stable.
+fn render_tags_links<W: Write>(
+    w: &mut W,
+    tags: &HashSet<Tag<'_>>,
+    links: &HashSet<Link<'_>>,
+) -> Result<(), BasicRendererError> {
+    let mut tags: Vec<_> = tags.iter().collect();
+    tags.sort();
+    for tag in tags {
+        write!(w, " #{}", tag)?;
+    }
+    let mut links: Vec<_> = links.iter().collect();
+    links.sort();
+    for link in links {
+        write!(w, " ^{}", link)?;
+    }
+    Ok(())
+}
+
+/// Writes metadata lines indented `depth` times: once for directives, twice for postings so that
+/// posting metadata is nested under its posting.
 fn render_key_value<W: Write>(
     renderer: &BasicRenderer,
     w: &mut W,
     kv: &HashMap<Cow<'_, str>, MetaValue<'_>>,
+    depth: usize,
 ) -> Result<(), BasicRendererError> {
+    // Sorted by key so the output doesn't depend on the map's iteration order.
+    let mut kv: Vec<_> = kv.iter().collect();
+    kv.sort_by_key(|(key, _)| *key);
     for (key, value) in kv {
-        write!(w, "\t{}: ", key)?;
+        if renderer.validate_meta_keys {
+            metadata::validate_meta_key(key)?;
+        }
+        write!(w, "{}{}: ", renderer.options.indent.repeat(depth), key)?;
         renderer.render(value, w)?;
         writeln!(w)?;
     }
@@ -105,8 +372,8 @@ impl<'a, W: Write> Renderer<&'a MetaValue<'_>, W> for BasicRenderer {
             MetaValue::Currency(curr) => write!(w, "{}", curr)?,
             MetaValue::Date(date) => write!(w, "{}", date)?,
             MetaValue::Number(num) => write!(w, "{}", num)?,
-            MetaValue::Tag(t) => write!(w, "{}", t)?,
-            MetaValue::Text(t) => write!(w, "{}", t)?,
+            MetaValue::Tag(t) => write!(w, "#{}", t)?,
+            MetaValue::Text(t) => write!(w, "\"{}\"", t)?,
         }
         Ok(())
     }
@@ -120,17 +387,11 @@ impl<'a, W: Write> Renderer<&'a Open<'_>, W> for BasicRenderer {
         for currency in open.currencies.iter() {
             write!(write, " {}", currency)?;
         }
-        match open.booking {
-            Some(Booking::Strict) => write!(write, r#" "STRICT""#)?,
-            Some(Booking::StrictWithSize) => write!(write, r#" "STRICT_WITH_SIZE""#)?,
-            Some(Booking::None) => write!(write, r#" "NONE""#)?,
-            Some(Booking::Average) => write!(write, r#" "AVERAGE""#)?,
-            Some(Booking::Fifo) => write!(write, r#" "FIFO""#)?,
-            Some(Booking::Lifo) => write!(write, r#" "LIFO""#)?,
-            None => {}
-        };
+        if let Some(booking) = &open.booking {
+            write!(write, r#" "{}""#, booking)?;
+        }
         writeln!(write)?;
-        render_key_value(self, write, &open.meta)?;
+        render_key_value(self, write, &open.meta, 1)?;
         Ok(())
     }
 }
@@ -141,7 +402,7 @@ impl<'a, W: Write> Renderer<&'a Close<'_>, W> for BasicRenderer {
         write!(write, "{} close ", close.date)?;
         self.render(&close.account, write)?;
         writeln!(write)?;
-        render_key_value(self, write, &close.meta)?;
+        render_key_value(self, write, &close.meta, 1)?;
         Ok(())
     }
 }
@@ -149,12 +410,10 @@ impl<'a, W: Write> Renderer<&'a Close<'_>, W> for BasicRenderer {
 impl<'a, W: Write> Renderer<&'a Account<'_>, W> for BasicRenderer {
     type Error = BasicRendererError;
     fn render(&self, account: &'a Account<'_>, write: &mut W) -> Result<(), Self::Error> {
-        write!(
-            write,
-            "{}:{}",
-            account.ty.default_name(),
-            account.parts.join(":")
-        )?;
+        write!(write, "{}", account.ty.default_name())?;
+        for part in &account.parts {
+            write!(write, "{}{}", self.account_separator, part)?;
+        }
         Ok(())
     }
 }
@@ -164,10 +423,19 @@ impl<'a, W: Write> Renderer<&'a Balance<'_>, W> for BasicRenderer {
     fn render(&self, balance: &'a Balance<'_>, w: &mut W) -> Result<(), Self::Error> {
         write!(w, "{} balance ", balance.date)?;
         self.render(&balance.account, w)?;
-        write!(w, "\t")?;
-        self.render(&balance.amount, w)?;
-        writeln!(w)?;
-        render_key_value(self, w, &balance.meta)?;
+        if balance.inclusive {
+            write!(w, ":*")?;
+        }
+        write!(
+            w,
+            "\t{}",
+            self.number(&balance.amount.num, &balance.amount.raw)
+        )?;
+        if let Some(tolerance) = balance.tolerance {
+            write!(w, " ~ {}", tolerance)?;
+        }
+        writeln!(w, " {}", balance.amount.currency)?;
+        render_key_value(self, w, &balance.meta, 1)?;
         Ok(())
     }
 }
@@ -175,7 +443,12 @@ impl<'a, W: Write> Renderer<&'a Balance<'_>, W> for BasicRenderer {
 impl<'a, W: Write> Renderer<&'a Amount<'_>, W> for BasicRenderer {
     type Error = BasicRendererError;
     fn render(&self, amount: &'a Amount<'_>, w: &mut W) -> Result<(), Self::Error> {
-        write!(w, "{} {}", amount.num, amount.currency)?;
+        write!(
+            w,
+            "{} {}",
+            self.number(&amount.num, &amount.raw),
+            amount.currency
+        )?;
         Ok(())
     }
 }
@@ -192,22 +465,20 @@ impl<'a, W: Write> Renderer<&'a Commodity<'_>, W> for BasicRenderer {
     type Error = BasicRendererError;
     fn render(&self, commodity: &'a Commodity<'_>, w: &mut W) -> Result<(), Self::Error> {
         writeln!(w, "{} commodity {}", commodity.date, commodity.name)?;
-        render_key_value(self, w, &commodity.meta)
+        render_key_value(self, w, &commodity.meta, 1)
     }
 }
 
 impl<'a, W: Write> Renderer<&'a Custom<'_>, W> for BasicRenderer {
     type Error = BasicRendererError;
     fn render(&self, custom: &'a Custom<'_>, w: &mut W) -> Result<(), Self::Error> {
-        write!(
-            w,
-            "{} custom \"{}\" {}",
-            custom.date,
-            custom.name,
-            custom.args.join(" ")
-        )?;
+        write!(w, "{} custom \"{}\"", custom.date, custom.name)?;
+        for arg in &custom.args {
+            write!(w, " ")?;
+            self.render(arg, w)?;
+        }
         writeln!(w)?;
-        render_key_value(self, w, &custom.meta)
+        render_key_value(self, w, &custom.meta, 1)
     }
 }
 
@@ -219,14 +490,14 @@ impl<'a, W: Write> Renderer<&'a Event<'_>, W> for BasicRenderer {
             "{} event \"{}\" \"{}\"",
             event.date, event.name, event.description
         )?;
-        render_key_value(self, w, &event.meta)
+        render_key_value(self, w, &event.meta, 1)
     }
 }
 
 impl<'a, W: Write> Renderer<&'a Include<'_>, W> for BasicRenderer {
     type Error = BasicRendererError;
     fn render(&self, include: &'a Include<'_>, w: &mut W) -> Result<(), Self::Error> {
-        writeln!(w, "include \"{}\'", include.filename)?;
+        writeln!(w, "include \"{}\"", include.filename)?;
         Ok(())
     }
 }
@@ -237,7 +508,7 @@ impl<'a, W: Write> Renderer<&'a Note<'_>, W> for BasicRenderer {
         write!(w, "{} note ", note.date)?;
         self.render(&note.account, w)?;
         writeln!(w, " {}", note.comment)?;
-        render_key_value(self, w, &note.meta)
+        render_key_value(self, w, &note.meta, 1)
     }
 }
 
@@ -249,7 +520,7 @@ impl<'a, W: Write> Renderer<&'a Pad<'_>, W> for BasicRenderer {
         write!(w, " ")?;
         self.render(&pad.pad_from_account, w)?;
         writeln!(w)?;
-        render_key_value(self, w, &pad.meta)
+        render_key_value(self, w, &pad.meta, 1)
     }
 }
 
@@ -271,7 +542,7 @@ impl<'a, W: Write> Renderer<&'a Price<'_>, W> for BasicRenderer {
         write!(w, "{} price {} ", price.date, price.currency)?;
         self.render(&price.amount, w)?;
         writeln!(w)?;
-        render_key_value(self, w, &price.meta)
+        render_key_value(self, w, &price.meta, 1)
     }
 }
 
@@ -283,7 +554,7 @@ impl<'a, W: Write> Renderer<&'a Query<'_>, W> for BasicRenderer {
             "{} query \"{}\" \"{}\"",
             query.date, query.name, query.query_string
         )?;
-        render_key_value(self, w, &query.meta)
+        render_key_value(self, w, &query.meta, 1)
     }
 }
 
@@ -295,31 +566,91 @@ impl<'a, W: Write> Renderer<&'a Transaction<'_>, W> for BasicRenderer {
             write!(w, " \"{}\"", payee)?;
         }
         write!(w, " \"{}\"", &transaction.narration)?;
-        for tag in &transaction.tags {
-            write!(w, " {}", tag)?;
+        render_tags_links(w, &transaction.tags, &transaction.links)?;
+        writeln!(w)?;
+        // Transaction metadata comes before the postings, so that it can't be mistaken for
+        // metadata of the last posting, which is indented one level deeper.
+        render_key_value(self, w, &transaction.meta, 1)?;
+        let mut postings: Vec<_> = transaction.postings.iter().collect();
+        if self.sort_postings {
+            postings.sort_by_cached_key(|posting| {
+                let mut account = Vec::new();
+                // Writing into a `Vec` can't fail.
+                let _ = self.render(&posting.account, &mut account);
+                (posting.units.num.is_none(), account)
+            });
         }
-        for link in &transaction.links {
-            write!(w, " {}", link)?;
+        let mut width = self.amount_width();
+        if self.options.align_transaction {
+            let mut widest = 0;
+            for posting in &postings {
+                widest = widest.max(self.posting_prefix(posting)?.chars().count());
+            }
+            width = Some(width.unwrap_or_default().max(widest + 2));
         }
-        writeln!(w)?;
-        for posting in &transaction.postings {
-            self.render(posting, w)?;
+        for posting in postings {
+            self.render_posting(posting, width, w)?;
         }
-        render_key_value(self, w, &transaction.meta)
+        Ok(())
     }
 }
 
-impl<'a, W: Write> Renderer<&'a Posting<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, posting: &'a Posting<'_>, w: &mut W) -> Result<(), Self::Error> {
-        write!(w, "\t")?;
+impl BasicRenderer {
+    /// Writes the flag and account of a posting.
+    fn render_posting_prefix<W: Write>(
+        &self,
+        posting: &Posting<'_>,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError> {
         if let Some(flag) = &posting.flag {
             write!(w, "{} ", flag)?;
         }
-        self.render(&posting.account, w)?;
-        write!(w, "\t")?;
+        self.render(&posting.account, w)
+    }
+
+    /// The flag and account of a posting, as written by
+    /// [`render_posting_prefix`](#method.render_posting_prefix).
+    fn posting_prefix(&self, posting: &Posting<'_>) -> Result<String, BasicRendererError> {
+        let mut prefix = Vec::new();
+        self.render_posting_prefix(posting, &mut prefix)?;
+        Ok(String::from_utf8_lossy(&prefix).into_owned())
+    }
+
+    /// The width that the flag and account of a posting are padded to so that its amount starts
+    /// at the configured amount column, if there is one.
+    fn amount_width(&self) -> Option<usize> {
+        let indent = self.options.indent.chars().count();
+        self.options
+            .amount_column
+            .map(|column| column.saturating_sub(indent))
+    }
+
+    /// Writes a posting, padding its flag and account to `width` characters if given. At least
+    /// two spaces are kept between the account and the amount.
+    fn render_posting<W: Write>(
+        &self,
+        posting: &Posting<'_>,
+        width: Option<usize>,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError> {
+        write!(w, "{}", self.options.indent)?;
+        match width {
+            Some(_) if posting.units.num.is_none() && posting.units.currency.is_none() => {
+                self.render_posting_prefix(posting, w)?;
+            }
+            Some(width) => {
+                let prefix = self.posting_prefix(posting)?;
+                let padding = width.saturating_sub(prefix.chars().count()).max(2);
+                write!(w, "{}{:padding$}", prefix, "", padding = padding)?;
+            }
+            None => {
+                self.render_posting_prefix(posting, w)?;
+                write!(w, "\t")?;
+            }
+        }
         self.render(&posting.units, w)?;
-        if let Some(cost) = &posting.cost {
+        // An empty `{}` doesn't change the meaning of the posting, so it is left out.
+        if let Some(cost) = posting.cost.as_ref().filter(|cost| !cost.is_empty()) {
             write!(w, " ")?;
             self.render(cost, w)?;
         }
@@ -328,7 +659,14 @@ impl<'a, W: Write> Renderer<&'a Posting<'_>, W> for BasicRenderer {
             self.render(price, w)?;
         }
         writeln!(w)?;
-        render_key_value(self, w, &posting.meta)
+        render_key_value(self, w, &posting.meta, 2)
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Posting<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, posting: &'a Posting<'_>, w: &mut W) -> Result<(), Self::Error> {
+        self.render_posting(posting, self.amount_width(), w)
     }
 }
 
@@ -341,29 +679,25 @@ impl<'a, W: Write> Renderer<&'a CostSpec<'_>, W> for BasicRenderer {
         } else {
             write!(w, "{{")?;
         }
-        let mut first = true;
+        let mut components = Vec::new();
 
-        if let (Some(cost), Some(currency)) =
-            (&cost.number_total.or(cost.number_per), &cost.currency)
-        {
-            write!(w, "{} {}", cost, currency)?;
-            first = false;
+        let (num, raw) = match cost.number_total {
+            Some(total) => (Some(total), &cost.raw_number_total),
+            None => (cost.number_per, &cost.raw_number_per),
+        };
+        if let (Some(num), Some(currency)) = (num, &cost.currency) {
+            components.push(format!("{} {}", self.number(&num, raw), currency));
         }
-
         if let Some(date) = &cost.date {
-            if !first {
-                write!(w, ", ")?;
-            }
-            write!(w, "{}", date)?;
-            first = false;
+            components.push(date.to_string());
         }
-
         if let Some(label) = &cost.label {
-            if !first {
-                write!(w, ", ")?;
-            }
-            write!(w, "{}", label)?;
+            components.push(format!("\"{}\"", label));
+        }
+        if cost.merge_cost {
+            components.push("*".to_string());
         }
+        write!(w, "{}", components.join(", "))?;
 
         if double_brackets {
             write!(w, "}}}}")?;
@@ -398,12 +732,15 @@ impl<'a, W: Write> Renderer<&'a IncompleteAmount<'_>, W> for BasicRenderer {
         incomplete_amount: &'a IncompleteAmount<'_>,
         w: &mut W,
     ) -> Result<(), Self::Error> {
-        match (&incomplete_amount.num, &incomplete_amount.currency) {
-            (Some(num), Some(currency)) => write!(w, "{} {}", num, currency),
-            (None, Some(currency)) => write!(w, "{}", currency),
-            (Some(num), None) => write!(w, "{}", num),
-            _ => write!(w, ""),
-        }?;
+        let num = incomplete_amount
+            .num
+            .map(|num| self.number(&num, &incomplete_amount.raw));
+        match (num, &incomplete_amount.currency) {
+            (Some(num), Some(currency)) => write!(w, "{} {}", num, currency)?,
+            (None, Some(currency)) => write!(w, "{}", currency)?,
+            (Some(num), None) => write!(w, "{}", num)?,
+            (None, None) => {}
+        }
         Ok(())
     }
 }
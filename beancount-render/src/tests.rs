@@ -1,23 +1,27 @@
-use crate::render;
-use beancount_parser::parse;
+use crate::{
+    display, render, render_or_source, render_to_string, write_csv, BasicRenderer,
+    BasicRendererError, RenderOptions, Renderer,
+};
+use beancount_core::metadata::MetaValue;
+use beancount_core::{
+    Account, AccountType, Amount, Booking, Close, Date, Directive, Ledger, Price, PriceSpec,
+};
+use beancount_parser::{parse, parse_with_options, ParseOptions};
 use indoc::indoc;
+use rust_decimal::Decimal;
 
 fn test_conversion(s: &str) -> anyhow::Result<()> {
     // First obtain the ledger
     let ledger = parse(s).unwrap();
 
     // Now render it
-    let mut rendered = Vec::new();
-    render(&mut rendered, &ledger)?;
-    let rendered = String::from_utf8(rendered).unwrap();
+    let rendered = render_to_string(&ledger)?;
 
     // Parse again
     let ledger_2 = parse(&rendered).unwrap();
 
     // Render to test for equality
-    let mut rendered_2 = Vec::new();
-    render(&mut rendered_2, &ledger_2)?;
-    let rendered_2 = String::from_utf8(rendered_2).unwrap();
+    let rendered_2 = render_to_string(&ledger_2)?;
 
     // Check for equality
     assert_eq!(rendered_2, rendered);
@@ -25,18 +29,139 @@ fn test_conversion(s: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_balance_tolerance() -> anyhow::Result<()> {
+    let source = "2014-08-09 balance Assets:Cash 562.00 ~ 0.002 USD\n";
+    test_conversion(source)?;
+
+    let ledger = parse(source).unwrap();
+    let mut rendered = Vec::new();
+    render(&mut rendered, &ledger)?;
+    let reparsed = parse(std::str::from_utf8(&rendered)?).unwrap();
+    match &reparsed.directives[0] {
+        Directive::Balance(balance) => {
+            assert_eq!(balance.amount, Amount::new(Decimal::new(56200, 2), "USD"));
+            assert_eq!(balance.tolerance, Some(Decimal::new(2, 3)));
+        }
+        other => panic!("expected a balance directive, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_raw_numbers() -> anyhow::Result<()> {
+    let source = "2020-01-01 balance Assets:Cash\t1,234.50 USD\n\
+                  2020-01-02 * \"Buy\"\n\
+                  \tAssets:Stock\t10 HOOL {{1,234.500 USD}}\n\
+                  \tAssets:Cash\t-1,234.50 USD @ 1.0 USD\n";
+    let options = ParseOptions {
+        keep_raw_numbers: true,
+        ..ParseOptions::default()
+    };
+    let ledger = parse_with_options(source, &options)?;
+    assert_eq!(render_to_string(&ledger)?, source);
+
+    // The literals don't affect equality.
+    match &ledger.directives[0] {
+        Directive::Balance(balance) => {
+            assert_eq!(balance.amount, Amount::new(Decimal::new(123450, 2), "USD"));
+        }
+        other => panic!("expected a balance directive, got {:?}", other),
+    }
+    assert_eq!(ledger, parse(source)?);
+
+    // Without the option, numbers are rendered normalized.
+    let rendered = render_to_string(&parse(source)?)?;
+    assert!(rendered.contains("\t1234.50 USD\n"), "{}", rendered);
+    Ok(())
+}
+
 #[test]
 fn test_close() -> anyhow::Result<()> {
     test_conversion("2016-11-28 close Liabilities:CreditCard:CapitalOne\n")?;
     Ok(())
 }
 
+#[test]
+fn test_close_with_meta() -> anyhow::Result<()> {
+    let source = indoc! {r#"
+        2016-11-28 close Liabilities:CreditCard:CapitalOne
+          reason: "fraud \"detected\""
+    "#};
+    test_conversion(source)?;
+
+    let ledger = parse(source).unwrap();
+    let mut rendered = Vec::new();
+    render(&mut rendered, &ledger)?;
+    let rendered = String::from_utf8(rendered)?;
+    let reparsed = parse(&rendered).unwrap();
+
+    match (&ledger.directives[0], &reparsed.directives[0]) {
+        (Directive::Close(original), Directive::Close(close)) => {
+            assert_eq!(close.date, original.date);
+            assert_eq!(close.account, original.account);
+            assert_eq!(close.meta, original.meta);
+        }
+        other => panic!("expected two close directives, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_sorted_meta() -> anyhow::Result<()> {
+    let ledger = parse(indoc! {r#"
+        2016-11-28 close Liabilities:CreditCard:CapitalOne
+          reason: "fraud"
+          agent: "joe"
+          ticket: 1234
+          category: "security"
+    "#})
+    .unwrap();
+
+    let render_once = || -> anyhow::Result<String> {
+        let mut rendered = Vec::new();
+        render(&mut rendered, &ledger)?;
+        Ok(String::from_utf8(rendered)?)
+    };
+    let rendered = render_once()?;
+    for _ in 0..5 {
+        assert_eq!(render_once()?, rendered);
+    }
+
+    let keys: Vec<_> = rendered
+        .lines()
+        .skip(1)
+        .map(|line| line.trim_start().split(':').next().unwrap())
+        .collect();
+    assert_eq!(keys, vec!["agent", "category", "reason", "ticket"]);
+    Ok(())
+}
+
 #[test]
 fn test_commodity_directive() -> anyhow::Result<()> {
     test_conversion("2012-01-01 commodity HOOL\n")?;
     Ok(())
 }
 
+#[test]
+fn test_custom_with_meta() -> anyhow::Result<()> {
+    let source = indoc! {r#"
+        2014-07-09 custom "budget" "monthly" TRUE 45.30 USD
+          owner: "joe"
+    "#};
+    test_conversion(source)?;
+
+    let mut rendered = Vec::new();
+    render(&mut rendered, &parse(source).unwrap())?;
+    let rendered = String::from_utf8(rendered).unwrap();
+    assert!(
+        rendered.starts_with("2014-07-09 custom \"budget\" \"monthly\" true 45.30 USD\n"),
+        "{}",
+        rendered
+    );
+    Ok(())
+}
+
 #[test]
 fn test_document() -> anyhow::Result<()> {
     test_conversion(
@@ -45,18 +170,92 @@ fn test_document() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_document_with_tags_links_and_meta() -> anyhow::Result<()> {
+    let source = indoc! {r#"
+        2013-11-03 document Liabilities:CreditCard "/home/joe/stmts/apr-2014.pdf" #statement ^apr-2014
+          pages: 3
+    "#};
+    let mut ledger = parse(source).unwrap();
+
+    let mut rendered = Vec::new();
+    render(&mut rendered, &ledger)?;
+    let rendered = String::from_utf8(rendered)?;
+    assert!(rendered.contains(" #statement ^apr-2014\n"));
+
+    let mut reparsed = parse(&rendered).unwrap();
+    ledger.strip_sources();
+    reparsed.strip_sources();
+    assert_eq!(reparsed, ledger);
+    match &ledger.directives[0] {
+        Directive::Document(document) => {
+            assert!(document.tags.contains("statement"));
+            assert!(document.links.contains("apr-2014"));
+            assert_eq!(document.meta["pages"], MetaValue::Number(3.into()));
+        }
+        other => panic!("expected a document, got {:?}", other),
+    }
+    Ok(())
+}
+
 #[test]
 fn test_event() -> anyhow::Result<()> {
     test_conversion("2014-07-09 event \"location\" \"Paris, France\"\n")?;
     Ok(())
 }
 
+#[test]
+fn test_include() -> anyhow::Result<()> {
+    test_conversion("include \"path/to/include/file.beancount\"\n")?;
+    Ok(())
+}
+
 #[test]
 fn test_note() -> anyhow::Result<()> {
     test_conversion("2013-11-03 note Liabilities:CreditCard \"Called about fraudulent card.\"\n")?;
     Ok(())
 }
 
+#[test]
+fn test_open_booking_methods() -> anyhow::Result<()> {
+    let bookings = [
+        Booking::Strict,
+        Booking::StrictWithSize,
+        Booking::None,
+        Booking::Average,
+        Booking::Fifo,
+        Booking::Lifo,
+    ];
+    for booking in bookings {
+        let source = format!("2014-05-01 open Assets:Brokerage HOOL \"{}\"\n", booking);
+        let ledger = parse(&source).unwrap();
+        let rendered = render_to_string(&ledger)?;
+        assert_eq!(rendered, source);
+        match &parse(&rendered).unwrap().directives[0] {
+            Directive::Open(open) => assert_eq!(open.booking, Some(booking)),
+            other => panic!("expected an open directive, got {:?}", other),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_open_booking_round_trip() -> anyhow::Result<()> {
+    let source = "2014-05-01 open Liabilities:CC USD \"FIFO\"\n";
+    test_conversion(source)?;
+
+    let rendered = render_to_string(&parse(source).unwrap())?;
+    let reparsed = parse(&rendered).unwrap();
+    match &reparsed.directives[0] {
+        Directive::Open(open) => assert_eq!(open.booking, Some(Booking::Fifo)),
+        other => panic!("expected an open directive, got {:?}", other),
+    }
+
+    // Only beancount's uppercase names are accepted, as they are the only ones rendered.
+    assert!(parse("2014-05-01 open Liabilities:CC USD \"fifo\"\n").is_err());
+    Ok(())
+}
+
 #[test]
 fn test_option() -> anyhow::Result<()> {
     test_conversion("option \"title\" \"Ed’s Personal Ledger\"\n")?;
@@ -97,3 +296,544 @@ fn test_transaction() -> anyhow::Result<()> {
     "#})?;
     Ok(())
 }
+
+#[test]
+fn test_posting_meta_indentation() -> anyhow::Result<()> {
+    let source = indoc! {r#"
+        2014-05-05 * "Cafe Mogador" "Lamb tagine with wine"
+          receipt: "2014-05-05.pdf"
+          Liabilities:CreditCard  -37.45 USD
+            statement: "May"
+          Expenses:Restaurant
+    "#};
+    let ledger = parse(source).unwrap();
+    let rendered = render_to_string(&ledger)?;
+    assert_eq!(
+        rendered,
+        "2014-05-05 * \"Cafe Mogador\" \"Lamb tagine with wine\"\n\
+         \treceipt: \"2014-05-05.pdf\"\n\
+         \tLiabilities:CreditCard\t-37.45 USD\n\
+         \t\tstatement: \"May\"\n\
+         \tExpenses:Restaurant\t\n"
+    );
+
+    let reparsed = parse(&rendered).unwrap();
+    let txn = reparsed.transactions().next().unwrap();
+    assert_eq!(
+        txn.meta["receipt"],
+        MetaValue::Text("2014-05-05.pdf".into())
+    );
+    assert!(!txn.meta.contains_key("statement"));
+    assert_eq!(
+        txn.postings[0].meta["statement"],
+        MetaValue::Text("May".into())
+    );
+    assert!(txn.postings[1].meta.is_empty());
+    test_conversion(source)
+}
+
+#[test]
+fn test_empty_cost() -> anyhow::Result<()> {
+    let ledger = parse(indoc! {r#"
+        2020-10-01 * "Sell"
+          Assets:Trading  -1 HOOL {}
+          Assets:Cash
+    "#})
+    .unwrap();
+    let rendered = render_to_string(&ledger)?;
+    assert!(
+        rendered.contains("\tAssets:Trading\t-1 HOOL\n"),
+        "{}",
+        rendered
+    );
+    assert!(!rendered.contains('{'), "{}", rendered);
+    Ok(())
+}
+
+#[test]
+fn test_merge_cost() -> anyhow::Result<()> {
+    let source = indoc! {r#"
+        2014-05-05 txn "Cafe Mogador" "Lamb tagine with wine"
+          Liabilities:CreditCard:CapitalOne  10 USD { 15 GBP, * } @ 20 GBP
+    "#};
+    test_conversion(source)?;
+
+    let rendered = render_to_string(&parse(source).unwrap())?;
+    assert!(
+        rendered.contains("10 USD {15 GBP, *} @ 20 GBP"),
+        "{}",
+        rendered
+    );
+    let reparsed = parse(&rendered).unwrap();
+    let cost = reparsed.transactions().next().unwrap().postings[0]
+        .cost
+        .clone()
+        .unwrap();
+    assert!(cost.merge_cost);
+    assert_eq!(cost.number_per, Some(Decimal::new(15, 0)));
+
+    let rendered =
+        render_to_string(&parse("2014-05-05 * \"Merge\"\n  Assets:Stock 10 HOOL {*}\n").unwrap())?;
+    assert!(rendered.contains("10 HOOL {*}\n"), "{}", rendered);
+    Ok(())
+}
+
+#[test]
+fn test_cost_label() -> anyhow::Result<()> {
+    let source = indoc! {r#"
+        2014-05-05 txn "Cafe Mogador" "Lamb tagine with wine"
+          Assets:Stock  10 HOOL {15 GBP, 2014-05-01, "lot1"}
+    "#};
+    test_conversion(source)?;
+
+    let rendered = render_to_string(&parse(source).unwrap())?;
+    assert!(
+        rendered.contains("10 HOOL {15 GBP, 2014-05-01, \"lot1\"}"),
+        "{}",
+        rendered
+    );
+    let reparsed = parse(&rendered).unwrap();
+    let cost = reparsed.transactions().next().unwrap().postings[0]
+        .cost
+        .clone()
+        .unwrap();
+    assert_eq!(cost.label.as_deref(), Some("lot1"));
+    Ok(())
+}
+
+#[test]
+fn test_total_price() -> anyhow::Result<()> {
+    let source = indoc! {r#"
+        2012-11-03 * "Transfer to account in Canada"
+          Assets:MyBank:Checking     -400.00 USD @@ 436.01 CAD
+          Assets:FR:SocGen:Checking   436.01 CAD
+    "#};
+    test_conversion(source)?;
+
+    let rendered = render_to_string(&parse(source).unwrap())?;
+    assert!(
+        rendered.contains("-400.00 USD @@ 436.01 CAD\n"),
+        "{}",
+        rendered
+    );
+    let reparsed = parse(&rendered).unwrap();
+    assert_eq!(
+        reparsed.transactions().next().unwrap().postings[0].price,
+        Some(PriceSpec::Total(
+            Amount::new(Decimal::new(43601, 2), "CAD").into()
+        ))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_blank_lines_between_directives() -> anyhow::Result<()> {
+    let source = indoc! {"
+        2016-11-28 close Liabilities:CreditCard:CapitalOne
+
+
+        2012-01-01 commodity HOOL
+    "};
+    test_conversion(source)?;
+
+    let mut rendered = Vec::new();
+    render(&mut rendered, &parse(source).unwrap())?;
+    assert_eq!(String::from_utf8(rendered)?, source);
+    Ok(())
+}
+
+fn render_account(renderer: &BasicRenderer, account: &Account<'_>) -> String {
+    let mut rendered = Vec::new();
+    renderer.render(account, &mut rendered).unwrap();
+    String::from_utf8(rendered).unwrap()
+}
+
+#[test]
+fn test_account_separator() {
+    let account = Account::builder()
+        .ty(AccountType::Assets)
+        .parts(vec!["US".into(), "Cash".into()])
+        .build();
+
+    let renderer = BasicRenderer::new();
+    assert!(renderer.round_trips());
+    assert_eq!(render_account(&renderer, &account), "Assets:US:Cash");
+
+    let renderer = BasicRenderer::new().with_account_separator('/');
+    assert!(!renderer.round_trips());
+    assert_eq!(render_account(&renderer, &account), "Assets/US/Cash");
+}
+
+#[test]
+fn test_other_flags() -> anyhow::Result<()> {
+    test_conversion(indoc! {r#"
+        2020-10-01 & "Flagged"
+          ? Assets:Trading             -1 USD
+          Income:Trading
+    "#})?;
+    Ok(())
+}
+
+#[test]
+fn test_typed_flags() -> anyhow::Result<()> {
+    test_conversion(indoc! {r#"
+        2020-10-01 S "Summarized"
+          M Assets:Trading             -1 USD
+          Income:Trading
+    "#})?;
+    Ok(())
+}
+
+#[test]
+fn test_sorted_postings() -> anyhow::Result<()> {
+    let ledger = parse(indoc! {r#"
+        2020-10-01 * "Groceries"
+          Expenses:Food:Vegetables    5.00 USD
+          Expenses:Food:Fruit         3.00 USD
+          Assets:Cash
+          Expenses:Food:Bread         2.00 USD
+    "#})
+    .unwrap();
+
+    let mut rendered = Vec::new();
+    BasicRenderer::new()
+        .with_sorted_postings(true)
+        .render(&ledger, &mut rendered)?;
+    let rendered = String::from_utf8(rendered)?;
+
+    let accounts: Vec<_> = rendered
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().next().unwrap())
+        .collect();
+    assert_eq!(
+        accounts,
+        vec![
+            "Expenses:Food:Bread",
+            "Expenses:Food:Fruit",
+            "Expenses:Food:Vegetables",
+            "Assets:Cash"
+        ]
+    );
+    test_conversion(&rendered)?;
+    Ok(())
+}
+
+#[test]
+fn test_aligned_flagged_postings() -> anyhow::Result<()> {
+    let ledger = parse(indoc! {r#"
+        2020-10-01 * "Opening"
+          P Assets:Cash    100.00 USD
+          Assets:Bank:Checking    5.00 USD
+          ! Liabilities:Card    -5.00 USD
+          Equity:Opening-Balances
+    "#})
+    .unwrap();
+
+    let mut rendered = Vec::new();
+    BasicRenderer::new()
+        .with_aligned_amounts(true)
+        .render(&ledger, &mut rendered)?;
+    let rendered = String::from_utf8(rendered)?;
+
+    let columns: Vec<_> = rendered
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.rfind("  ").map(|separator| separator + 2))
+        .collect();
+    assert_eq!(columns.len(), 3);
+    assert!(columns.iter().all(|&column| column == columns[0]));
+    assert!(rendered.contains("\tEquity:Opening-Balances\n"));
+    test_conversion(&rendered)?;
+    Ok(())
+}
+
+const VARYING_ACCOUNTS: &str = indoc! {r#"
+    2020-10-01 * "Paycheck"
+      Assets:Cash    100.00 USD
+      Income:Employer:Acme-Corporation:Salary:Base-Pay    -1000.00 USD
+      Expenses:Taxes:Federal    900.00 USD
+        note: "withheld"
+    "#};
+
+/// The columns at which the amounts of the rendered postings start.
+fn amount_columns(rendered: &str) -> Vec<usize> {
+    rendered
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim_start().starts_with("note"))
+        .filter_map(|line| line.rfind("  ").map(|separator| separator + 2))
+        .collect()
+}
+
+#[test]
+fn test_render_options_align_transaction() -> anyhow::Result<()> {
+    let ledger = parse(VARYING_ACCOUNTS).unwrap();
+    let rendered = BasicRenderer::new()
+        .with_options(RenderOptions {
+            indent: "  ",
+            align_transaction: true,
+            ..RenderOptions::default()
+        })
+        .render_to_string(&ledger)?;
+
+    let columns = amount_columns(&rendered);
+    assert_eq!(columns.len(), 3);
+    assert!(columns.iter().all(|&column| column == columns[0]));
+    assert!(
+        rendered.contains("\n  Income:Employer:Acme-Corporation:Salary:Base-Pay  -1000.00 USD\n")
+    );
+    assert!(rendered.contains("\n    note: \"withheld\"\n"));
+    assert!(!rendered.contains('\t'));
+    test_conversion(&rendered)?;
+    Ok(())
+}
+
+#[test]
+fn test_render_options_amount_column() -> anyhow::Result<()> {
+    let ledger = parse(VARYING_ACCOUNTS).unwrap();
+    let rendered = BasicRenderer::new()
+        .with_options(RenderOptions {
+            indent: "  ",
+            amount_column: Some(30),
+            align_transaction: false,
+            ..RenderOptions::default()
+        })
+        .render_to_string(&ledger)?;
+
+    // Accounts that reach past the column are followed by two spaces instead.
+    assert_eq!(amount_columns(&rendered), vec![30, 52, 30]);
+    test_conversion(&rendered)?;
+    Ok(())
+}
+
+#[test]
+fn test_default_render_options() -> anyhow::Result<()> {
+    let ledger = parse(VARYING_ACCOUNTS).unwrap();
+    let rendered = render_to_string(&ledger)?;
+    assert!(rendered.contains("\n\tAssets:Cash\t100.00 USD\n"));
+    assert!(rendered.contains("\n\t\tnote: \"withheld\"\n"));
+    Ok(())
+}
+
+#[test]
+fn test_render_commas() -> anyhow::Result<()> {
+    let source = indoc! {r#"
+        2020-01-01 * "Buy"
+          Assets:Stock  1000 HOOL {1234567.89 USD} @ 1234567.89 USD
+          Assets:Cash  -1234567890.12 USD
+    "#};
+    let ledger = parse(source).unwrap();
+
+    let rendered = render_to_string(&ledger)?;
+    assert!(rendered.contains("\t1000 HOOL {1234567.89 USD} @ 1234567.89 USD\n"));
+    assert!(rendered.contains("\t-1234567890.12 USD\n"));
+
+    let with_commas = BasicRenderer::new().with_options(RenderOptions {
+        render_commas: true,
+        ..RenderOptions::default()
+    });
+    let rendered = with_commas.render_to_string(&ledger)?;
+    assert!(rendered.contains("\t1,000 HOOL {1,234,567.89 USD} @ 1,234,567.89 USD\n"));
+    assert!(rendered.contains("\t-1,234,567,890.12 USD\n"));
+    assert_eq!(
+        with_commas
+            .render_to_string(&parse("2020-01-01 balance Assets:Cash 999.12345 USD\n").unwrap())?,
+        "2020-01-01 balance Assets:Cash\t999.12345 USD\n"
+    );
+
+    // The grouped numbers parse back to the same ledger.
+    let mut reparsed = parse(&rendered).unwrap();
+    let mut ledger = ledger;
+    reparsed.strip_sources();
+    ledger.strip_sources();
+    assert_eq!(reparsed, ledger);
+    Ok(())
+}
+
+#[test]
+fn test_price_from_rate() -> anyhow::Result<()> {
+    let price = Price::from_rate(
+        Date::from_str_unchecked("2020-01-01"),
+        "USD",
+        Amount::new(Decimal::new(108, 2), "CAD"),
+    );
+    let ledger = Ledger::builder()
+        .directives(vec![Directive::Price(price)])
+        .build();
+
+    let rendered = render_to_string(&ledger)?;
+    assert_eq!(rendered, "2020-01-01 price USD 1.08 CAD\n");
+    test_conversion(&rendered)?;
+    Ok(())
+}
+
+#[test]
+fn test_display() -> anyhow::Result<()> {
+    let ledger = parse(indoc!(
+        r#"
+        2014-05-01 open Assets:Cash USD
+        2014-05-05 * "Cafe Mogador" "Lamb tagine with wine"
+            Liabilities:CreditCard  -37.45 USD
+            Expenses:Restaurant
+
+        2014-06-01 balance Assets:Cash 0 USD
+        "#
+    ))
+    .unwrap();
+    let rendered = render_to_string(&ledger)?;
+    assert_eq!(display(&ledger).to_string(), rendered);
+    assert_eq!(format!("{}", display(&ledger)), rendered);
+
+    let unsupported = Ledger {
+        directives: vec![Directive::Unsupported("".into())],
+    };
+    let mut formatted = String::new();
+    let result =
+        std::fmt::Write::write_fmt(&mut formatted, format_args!("{}", display(&unsupported)));
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_error_names_directive() {
+    let mut ledger = parse("2014-05-01 open Assets:Cash\n").unwrap();
+    ledger.directives.push(Directive::Unsupported("".into()));
+    let err = render_to_string(&ledger).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "could not render unsupported directive 1 of the ledger"
+    );
+    match err {
+        BasicRendererError::Directive {
+            index: 1,
+            kind: "unsupported",
+            source,
+        } => assert!(matches!(*source, BasicRendererError::Unsupported)),
+        other => panic!("expected a directive error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_unsupported_source() -> anyhow::Result<()> {
+    let source =
+        "* Accounts\n2014-05-01 open Assets:Cash\n** Closed\n2014-05-02 close Assets:Cash\n";
+    let ledger = parse(source).unwrap();
+    assert_eq!(
+        ledger.directives[0],
+        Directive::Unsupported("* Accounts\n".into())
+    );
+    assert_eq!(render_to_string(&ledger)?, source);
+
+    let owned = ledger.into_owned();
+    assert_eq!(render_to_string(&owned)?, source);
+    Ok(())
+}
+
+#[test]
+fn test_skip_unsupported() -> anyhow::Result<()> {
+    let mut ledger = parse("2014-05-01 open Assets:Cash\n").unwrap();
+    ledger.directives.push(Directive::Unsupported("".into()));
+    ledger
+        .directives
+        .extend(parse("2014-05-02 close Assets:Cash\n").unwrap().directives);
+
+    assert!(BasicRenderer::new().render_to_string(&ledger).is_err());
+
+    let rendered = BasicRenderer::new()
+        .with_skip_unsupported(true)
+        .render_to_string(&ledger)?;
+    assert_eq!(
+        rendered,
+        "2014-05-01 open Assets:Cash\n; unsupported directive\n2014-05-02 close Assets:Cash\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_write_csv() -> anyhow::Result<()> {
+    let ledger = parse(indoc! {r#"
+        2020-10-01 * "Broker, Inc." "Sell \"HOOL\""
+          Assets:Trading             -1 HOOL {500.00 USD} @ 585.00 USD
+          Assets:Trading         585.00 USD
+          Income:Trading:Gains
+    "#})
+    .unwrap();
+
+    let mut csv = Vec::new();
+    write_csv(&mut csv, &ledger)?;
+    let csv = String::from_utf8(csv)?;
+    let rows: Vec<_> = csv.lines().collect();
+    assert_eq!(rows.len(), 4);
+    assert_eq!(
+        rows[0],
+        "date,flag,payee,narration,account,amount,currency,cost,price"
+    );
+    assert_eq!(
+        rows[1],
+        r#"2020-10-01,*,"Broker, Inc.","Sell ""HOOL""",Assets:Trading,-1,HOOL,{500.00 USD},@ 585.00 USD"#
+    );
+    assert_eq!(
+        rows[3],
+        r#"2020-10-01,*,"Broker, Inc.","Sell ""HOOL""",Income:Trading:Gains,,,,"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_meta_key_validation() -> anyhow::Result<()> {
+    let close = |key: &'static str| {
+        let meta = vec![(key.into(), MetaValue::Bool(true))]
+            .into_iter()
+            .collect();
+        Ledger::builder()
+            .directives(vec![Directive::Close(
+                Close::builder()
+                    .date(Date::from_str_unchecked("2016-11-28"))
+                    .account(
+                        Account::builder()
+                            .ty(AccountType::Assets)
+                            .parts(vec!["Cash".into()])
+                            .build(),
+                    )
+                    .meta(meta)
+                    .build(),
+            )])
+            .build()
+    };
+    let renderer = BasicRenderer::new().with_meta_key_validation(true);
+
+    renderer.render(&close("foo-bar"), &mut Vec::new())?;
+    match renderer.render(&close("Foo"), &mut Vec::new()) {
+        Err(BasicRendererError::Directive { source, .. }) => match *source {
+            BasicRendererError::InvalidMetaKey(err) => assert_eq!(err.key, "Foo"),
+            other => panic!("expected an invalid key error, got {:?}", other),
+        },
+        other => panic!("expected a directive error, got {:?}", other),
+    }
+    // Without validation the key is written as-is.
+    BasicRenderer::new().render(&close("Foo"), &mut Vec::new())?;
+    Ok(())
+}
+
+#[test]
+fn test_render_or_source() -> anyhow::Result<()> {
+    let source = indoc! {r#"
+        2020-10-01 *   "Groceries"  ; weekly shop
+          Expenses:Food    5.00 USD
+          Assets:Cash
+    "#};
+    let mut ledger = parse(source).unwrap();
+
+    let mut rendered = Vec::new();
+    render_or_source(&mut rendered, &ledger.directives[0])?;
+    assert_eq!(String::from_utf8(rendered)?, source);
+
+    ledger.strip_sources();
+    let mut rendered = Vec::new();
+    render_or_source(&mut rendered, &ledger.directives[0])?;
+    let mut expected = Vec::new();
+    render(&mut expected, &ledger)?;
+    assert_eq!(rendered, expected);
+    Ok(())
+}